@@ -0,0 +1,7 @@
+#![no_main]
+use auto_wasi::WasiVersion;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|binary: &[u8]| {
+    let _ = WasiVersion::detect(binary);
+});
@@ -0,0 +1,15 @@
+#![no_main]
+use auto_wasi::WasiVersion;
+use libfuzzer_sys::fuzz_target;
+
+// Versions detected from imports are among the versions `detect_all` collects, and don't depend
+// on the fallback.
+fuzz_target!(|input: (WasiVersion, &[u8])| {
+    let (fallback, binary) = input;
+    if let Ok(Some(version)) = WasiVersion::try_detect(binary) {
+        let versions = WasiVersion::detect_all(binary).expect("detect_all failed");
+        assert!(versions.contains(&version));
+        let detected = WasiVersion::detect_with_fallback(binary, fallback).ok();
+        assert_eq!(detected, Some(version));
+    }
+});
@@ -0,0 +1,98 @@
+//! The functions provided by each WASI version.
+use crate::WasiVersion;
+use wasmtime::{FuncType, ValType};
+
+/// A function provided by a WASI version.
+pub(crate) struct Function {
+    pub name: &'static str,
+    params: &'static [ValType],
+    results: &'static [ValType],
+}
+
+impl Function {
+    pub fn ty(&self) -> FuncType {
+        FuncType::new(self.params.iter().cloned(), self.results.iter().cloned())
+    }
+}
+
+macro_rules! functions {
+    ($($name:ident($($param:ident),*) $(-> $result:ident)?;)*) => {
+        &[$(Function {
+            name: stringify!($name),
+            params: &[$(ValType::$param),*],
+            results: &[$(ValType::$result)?],
+        }),*]
+    };
+}
+
+/// The functions of `wasi_snapshot_preview1`.
+///
+/// `wasi_unstable` provides functions of the same names and core signatures, the differences
+/// between them are limited to the layout of the structures passed through memory.
+static SNAPSHOT: &[Function] = functions! {
+    args_get(I32, I32) -> I32;
+    args_sizes_get(I32, I32) -> I32;
+    clock_res_get(I32, I32) -> I32;
+    clock_time_get(I32, I64, I32) -> I32;
+    environ_get(I32, I32) -> I32;
+    environ_sizes_get(I32, I32) -> I32;
+    fd_advise(I32, I64, I64, I32) -> I32;
+    fd_allocate(I32, I64, I64) -> I32;
+    fd_close(I32) -> I32;
+    fd_datasync(I32) -> I32;
+    fd_fdstat_get(I32, I32) -> I32;
+    fd_fdstat_set_flags(I32, I32) -> I32;
+    fd_fdstat_set_rights(I32, I64, I64) -> I32;
+    fd_filestat_get(I32, I32) -> I32;
+    fd_filestat_set_size(I32, I64) -> I32;
+    fd_filestat_set_times(I32, I64, I64, I32) -> I32;
+    fd_pread(I32, I32, I32, I64, I32) -> I32;
+    fd_prestat_dir_name(I32, I32, I32) -> I32;
+    fd_prestat_get(I32, I32) -> I32;
+    fd_pwrite(I32, I32, I32, I64, I32) -> I32;
+    fd_read(I32, I32, I32, I32) -> I32;
+    fd_readdir(I32, I32, I32, I64, I32) -> I32;
+    fd_renumber(I32, I32) -> I32;
+    fd_seek(I32, I64, I32, I32) -> I32;
+    fd_sync(I32) -> I32;
+    fd_tell(I32, I32) -> I32;
+    fd_write(I32, I32, I32, I32) -> I32;
+    path_create_directory(I32, I32, I32) -> I32;
+    path_filestat_get(I32, I32, I32, I32, I32) -> I32;
+    path_filestat_set_times(I32, I32, I32, I32, I64, I64, I32) -> I32;
+    path_link(I32, I32, I32, I32, I32, I32, I32) -> I32;
+    path_open(I32, I32, I32, I32, I32, I64, I64, I32, I32) -> I32;
+    path_readlink(I32, I32, I32, I32, I32, I32) -> I32;
+    path_remove_directory(I32, I32, I32) -> I32;
+    path_rename(I32, I32, I32, I32, I32, I32) -> I32;
+    path_symlink(I32, I32, I32, I32, I32) -> I32;
+    path_unlink_file(I32, I32, I32) -> I32;
+    poll_oneoff(I32, I32, I32, I32) -> I32;
+    proc_exit(I32);
+    proc_raise(I32) -> I32;
+    random_get(I32, I32) -> I32;
+    sched_yield() -> I32;
+    sock_recv(I32, I32, I32, I32, I32, I32) -> I32;
+    sock_send(I32, I32, I32, I32, I32) -> I32;
+    sock_shutdown(I32, I32) -> I32;
+};
+
+/// Returns the functions provided by the WASI version, sorted by name.
+///
+/// Empty for versions which don't import functions from a single core module, and for the WASIX
+/// extensions whose signatures aren't checked.
+pub(crate) fn functions(version: &WasiVersion) -> &'static [Function] {
+    match version {
+        WasiVersion::Snapshot0 | WasiVersion::Snapshot1 => SNAPSHOT,
+        WasiVersion::Preview2 | WasiVersion::Wasix32v1 | WasiVersion::Wasix64v1 => &[],
+    }
+}
+
+/// Returns the function of the WASI version with the provided name, if any.
+pub(crate) fn function(version: &WasiVersion, name: &str) -> Option<&'static Function> {
+    let functions = functions(version);
+    functions
+        .binary_search_by(|function| function.name.cmp(name))
+        .ok()
+        .map(|index| &functions[index])
+}
@@ -0,0 +1,212 @@
+use crate::{AutoWasi, CapturedIo, RequiredCapabilities, WasiDetectError, WasiVersion};
+use std::path::PathBuf;
+use wasi_common::{WasiCtx, WasiCtxBuilder};
+use wasmtime::Store;
+
+/// A builder for an [`AutoWasi`](crate::AutoWasi) and the [`WasiCtx`](wasi_common::WasiCtx)
+/// backing it.
+///
+/// Stdio is connected to null devices unless [`inherit_stdio`](Self::inherit_stdio),
+/// `stdin_bytes` or [`capture_stdio`](Self::capture_stdio) is called.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # use wasmtime::*;
+/// # fn test() -> anyhow::Result<()> {
+/// # let wasm = wat::parse_str("(module)")?;
+/// let store = Store::default();
+/// let wasi = AutoWasiBuilder::new()
+///     .args(["app", "--verbose"])
+///     .envs([("RUST_LOG", "debug")])
+///     .inherit_stdio()
+///     .detect(&store, wasm)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AutoWasiBuilder {
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    preopens: Vec<(PathBuf, PathBuf)>,
+    inherit_stdio: bool,
+    null_stdio: bool,
+    #[cfg(feature = "stdin")]
+    stdin: Option<Vec<u8>>,
+    capture: Option<CapturedIo>,
+}
+
+impl AutoWasiBuilder {
+    /// Creates a builder without any args, envs or preopened directories.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the args passed to the module.
+    pub fn args<'a>(&mut self, args: impl IntoIterator<Item = &'a str>) -> &mut Self {
+        self.args.extend(args.into_iter().map(String::from));
+        self
+    }
+
+    /// Adds the environment variables passed to the module.
+    pub fn envs<'a>(&mut self, envs: impl IntoIterator<Item = (&'a str, &'a str)>) -> &mut Self {
+        let envs = envs
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()));
+        self.envs.extend(envs);
+        self
+    }
+
+    /// Opens the host directory at `path` for the module as `guest_path`.
+    ///
+    /// The directory is opened when the [`AutoWasi`](crate::AutoWasi) is built.
+    pub fn preopen_dir(
+        &mut self,
+        path: impl Into<PathBuf>,
+        guest_path: impl Into<PathBuf>,
+    ) -> &mut Self {
+        self.preopens.push((path.into(), guest_path.into()));
+        self
+    }
+
+    /// Connects the module's stdin, stdout and stderr to the host's.
+    pub fn inherit_stdio(&mut self) -> &mut Self {
+        self.inherit_stdio = true;
+        self
+    }
+
+    /// Connects the module's stdin, stdout and stderr to null devices.
+    ///
+    /// This is the default, calling it makes combining it with
+    /// [`inherit_stdio`](Self::inherit_stdio) an error.
+    pub fn null_stdio(&mut self) -> &mut Self {
+        self.null_stdio = true;
+        self
+    }
+
+    /// Makes the module read the bytes from stdin, reaching the end of the file after them.
+    ///
+    /// Each built context reads the bytes from the start. They're backed by a temporary file,
+    /// so guests polling stdin with `poll_oneoff` see it as readable, unlike with a
+    /// [`ReadPipe`](wasi_common::virtfs::pipe::ReadPipe). This takes precedence over the stdin
+    /// of [`inherit_stdio`](Self::inherit_stdio).
+    ///
+    /// Requires the `stdin` feature.
+    #[cfg(feature = "stdin")]
+    pub fn stdin_bytes(&mut self, bytes: impl Into<Vec<u8>>) -> &mut Self {
+        self.stdin = Some(bytes.into());
+        self
+    }
+
+    /// Makes the module read the string from stdin, like [`stdin_bytes`](Self::stdin_bytes).
+    #[cfg(feature = "stdin")]
+    pub fn stdin_str(&mut self, stdin: &str) -> &mut Self {
+        self.stdin_bytes(stdin)
+    }
+
+    /// Writes the module's stdout and stderr to the buffers, taking precedence over the stdout
+    /// and stderr of [`inherit_stdio`](Self::inherit_stdio).
+    pub fn capture_stdio(&mut self, io: &CapturedIo) -> &mut Self {
+        self.capture = Some(io.clone());
+        self
+    }
+
+    /// Returns a copy of this builder without the resources the capabilities don't need.
+    ///
+    /// Preopened directories are only kept for [`Filesystem`](crate::Capability::Filesystem),
+    /// envs for [`Environment`](crate::Capability::Environment), args for
+    /// [`Args`](crate::Capability::Args) and inherited stdio for
+    /// [`Stdio`](crate::Capability::Stdio).
+    ///
+    /// The bytes of `stdin_bytes` and the buffers of [`capture_stdio`](Self::capture_stdio) are
+    /// always kept, as they don't give the module access to anything of the host's.
+    pub fn restrict(&self, capabilities: &RequiredCapabilities) -> Self {
+        let mut builder = self.clone();
+        if !capabilities.filesystem() {
+            builder.preopens.clear();
+        }
+        if !capabilities.environment() {
+            builder.envs.clear();
+        }
+        if !capabilities.args() {
+            builder.args.clear();
+        }
+        if !capabilities.stdio() {
+            builder.inherit_stdio = false;
+        }
+        builder
+    }
+
+    /// Builds an [`AutoWasi`](crate::AutoWasi) for the WASI version detected from the binary.
+    pub fn detect<T: AsRef<[u8]>>(
+        &self,
+        store: &Store,
+        binary: T,
+    ) -> Result<AutoWasi, WasiDetectError> {
+        let version = WasiVersion::detect(binary)?;
+        self.build(store, version)
+    }
+
+    /// Builds an [`AutoWasi`](crate::AutoWasi) for the provided WASI version.
+    pub fn build(&self, store: &Store, version: WasiVersion) -> Result<AutoWasi, WasiDetectError> {
+        AutoWasi::new(store, self.ctx()?, version)
+    }
+
+    /// Builds a [`MixedWasi`](crate::MixedWasi) linking both snapshots to one context built
+    /// from this builder's options.
+    #[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+    pub fn build_mixed(&self, store: &Store) -> Result<crate::MixedWasi, WasiDetectError> {
+        Ok(crate::MixedWasi::new(store, self.ctx()?))
+    }
+
+    /// Builds the [`WasiCtx`](wasi_common::WasiCtx) without creating an
+    /// [`AutoWasi`](crate::AutoWasi).
+    pub fn ctx(&self) -> Result<WasiCtx, WasiDetectError> {
+        let stdio = [
+            ("inherit_stdio", self.inherit_stdio),
+            #[cfg(feature = "stdin")]
+            ("stdin_bytes", self.stdin.is_some()),
+            ("capture_stdio", self.capture.is_some()),
+        ];
+        if self.null_stdio {
+            if let Some(&(first, _)) = stdio.iter().find(|(_, set)| *set) {
+                return Err(WasiDetectError::ConflictingOptions {
+                    first,
+                    second: "null_stdio",
+                });
+            }
+        }
+
+        let mut builder = WasiCtxBuilder::new();
+        builder.args(&self.args).envs(&self.envs);
+        if self.inherit_stdio {
+            builder.inherit_stdio();
+        }
+        #[cfg(feature = "stdin")]
+        if let Some(stdin) = &self.stdin {
+            builder.stdin(stdin_file(stdin)?);
+        }
+        if let Some(capture) = &self.capture {
+            capture.capture_stdio(&mut builder);
+        }
+        for (path, guest_path) in &self.preopens {
+            let dir = wasi_common::preopen_dir(path).map_err(|error| WasiDetectError::Preopen {
+                path: path.clone(),
+                error,
+            })?;
+            builder.preopened_dir(dir, guest_path);
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+/// Creates an unnamed temporary file holding the bytes, positioned at its start.
+#[cfg(feature = "stdin")]
+fn stdin_file(bytes: &[u8]) -> Result<wasi_common::OsFile, WasiDetectError> {
+    use std::convert::TryFrom;
+    use std::io::{Seek, SeekFrom, Write};
+
+    let mut file = tempfile::tempfile()?;
+    file.write_all(bytes)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(wasi_common::OsFile::try_from(file)?)
+}
@@ -0,0 +1,90 @@
+use crate::{WasiDetectError, WasiVersion};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+#[cfg(not(feature = "sync"))]
+type Lock<T> = std::cell::RefCell<T>;
+#[cfg(feature = "sync")]
+type Lock<T> = std::sync::RwLock<T>;
+
+type Versions = HashMap<[u8; 32], WasiVersion>;
+
+/// A cache of detected WASI versions, keyed by the SHA-256 of each binary.
+///
+/// Detecting a binary the cache has seen before only hashes it instead of parsing it again.
+/// Failed detections aren't cached. With the `sync` feature the cache is guarded by a
+/// [`RwLock`](std::sync::RwLock) and can be shared between threads.
+#[derive(Debug, Default)]
+pub struct WasiVersionCache {
+    versions: Lock<Versions>,
+}
+
+impl WasiVersionCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Detects the WASI version used by the binary like
+    /// [`WasiVersion::detect`](crate::WasiVersion::detect), returning the cached version if the
+    /// binary was detected before.
+    pub fn detect_cached(&self, binary: &[u8]) -> Result<WasiVersion, WasiDetectError> {
+        let key = Self::key(binary);
+        if let Some(version) = self.read().get(&key) {
+            return Ok(*version);
+        }
+
+        let version = WasiVersion::detect(binary)?;
+        self.write().insert(key, version);
+        Ok(version)
+    }
+
+    /// Removes the cached version of the binary, if any.
+    pub fn invalidate(&self, binary: &[u8]) {
+        self.write().remove(&Self::key(binary));
+    }
+
+    /// Removes every cached version.
+    pub fn invalidate_all(&self) {
+        self.write().clear();
+    }
+
+    /// Returns the number of cached versions.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns whether no versions are cached.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    fn key(binary: &[u8]) -> [u8; 32] {
+        Sha256::digest(binary).into()
+    }
+
+    #[cfg(not(feature = "sync"))]
+    fn read(&self) -> std::cell::Ref<'_, Versions> {
+        self.versions.borrow()
+    }
+
+    #[cfg(not(feature = "sync"))]
+    fn write(&self) -> std::cell::RefMut<'_, Versions> {
+        self.versions.borrow_mut()
+    }
+
+    // The map is valid after any panic while it's locked, so poisoning is ignored.
+    #[cfg(feature = "sync")]
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Versions> {
+        self.versions
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    #[cfg(feature = "sync")]
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Versions> {
+        self.versions
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+
+/// A kind of host access a WASI function needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Capability {
+    /// Reading and writing file descriptors the module is given, such as stdio, with `fd_*`
+    /// functions.
+    Stdio,
+    /// Opening paths and listing preopened directories, with `path_*`, `fd_prestat_*` and
+    /// `fd_readdir`.
+    Filesystem,
+    /// Reading clocks and waiting with `clock_*` and `poll_oneoff`.
+    Clocks,
+    /// Reading entropy with `random_get`.
+    Random,
+    /// Using sockets with `sock_*`.
+    Sockets,
+    /// Reading the environment with `environ_*`.
+    Environment,
+    /// Reading the command line args with `args_*`.
+    Args,
+}
+
+impl Capability {
+    /// Returns the capability needed by the `wasi_unstable` or `wasi_snapshot_preview1`
+    /// function, or `None` for functions that don't need any such as `proc_exit`.
+    pub fn from_function(name: &str) -> Option<Self> {
+        if name.starts_with("path_") || name.starts_with("fd_prestat_") || name == "fd_readdir" {
+            Some(Self::Filesystem)
+        } else if name.starts_with("fd_") {
+            Some(Self::Stdio)
+        } else if name.starts_with("clock_") || name == "poll_oneoff" {
+            Some(Self::Clocks)
+        } else if name == "random_get" {
+            Some(Self::Random)
+        } else if name.starts_with("sock_") {
+            Some(Self::Sockets)
+        } else if name.starts_with("environ_") {
+            Some(Self::Environment)
+        } else if name.starts_with("args_") {
+            Some(Self::Args)
+        } else {
+            None
+        }
+    }
+}
+
+/// Formats the capability in lowercase, such as `filesystem`.
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdio => write!(f, "stdio"),
+            Self::Filesystem => write!(f, "filesystem"),
+            Self::Clocks => write!(f, "clocks"),
+            Self::Random => write!(f, "random"),
+            Self::Sockets => write!(f, "sockets"),
+            Self::Environment => write!(f, "environment"),
+            Self::Args => write!(f, "args"),
+        }
+    }
+}
+
+/// The capabilities a binary needs, inferred from the WASI functions it imports.
+///
+/// Created by [`WasiVersion::required_capabilities`](crate::WasiVersion::required_capabilities).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RequiredCapabilities {
+    functions: Vec<(Capability, String)>,
+}
+
+impl RequiredCapabilities {
+    pub(crate) fn add(&mut self, name: &str) {
+        if let Some(capability) = Capability::from_function(name) {
+            self.functions.push((capability, name.to_string()));
+        }
+    }
+
+    /// Returns whether any imported function needs the capability.
+    pub fn requires(&self, capability: Capability) -> bool {
+        self.functions.iter().any(|(c, _)| *c == capability)
+    }
+
+    /// Returns the capabilities needed, sorted and without duplicates.
+    pub fn capabilities(&self) -> Vec<Capability> {
+        let mut capabilities: Vec<_> = self.functions.iter().map(|(c, _)| *c).collect();
+        capabilities.sort();
+        capabilities.dedup();
+        capabilities
+    }
+
+    /// Returns the imported functions needing the capability, in the order they're imported.
+    pub fn functions(&self, capability: Capability) -> impl Iterator<Item = &str> + '_ {
+        self.functions
+            .iter()
+            .filter(move |(c, _)| *c == capability)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Returns whether the binary uses file descriptors it's given, such as stdio.
+    pub fn stdio(&self) -> bool {
+        self.requires(Capability::Stdio)
+    }
+
+    /// Returns whether the binary opens paths or lists preopened directories.
+    pub fn filesystem(&self) -> bool {
+        self.requires(Capability::Filesystem)
+    }
+
+    /// Returns whether the binary reads clocks or waits.
+    pub fn clocks(&self) -> bool {
+        self.requires(Capability::Clocks)
+    }
+
+    /// Returns whether the binary reads entropy.
+    pub fn random(&self) -> bool {
+        self.requires(Capability::Random)
+    }
+
+    /// Returns whether the binary uses sockets.
+    pub fn sockets(&self) -> bool {
+        self.requires(Capability::Sockets)
+    }
+
+    /// Returns whether the binary reads the environment.
+    pub fn environment(&self) -> bool {
+        self.requires(Capability::Environment)
+    }
+
+    /// Returns whether the binary reads its command line args.
+    pub fn args(&self) -> bool {
+        self.requires(Capability::Args)
+    }
+}
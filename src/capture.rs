@@ -0,0 +1,217 @@
+use crate::{AutoWasi, WasiDetectError};
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Arc, RwLock};
+use wasi_common::virtfs::pipe::WritePipe;
+use wasi_common::{WasiCtx, WasiCtxBuilder};
+
+/// In-memory buffers a guest's stdout and stderr are written to.
+///
+/// Create a context writing to the buffers with [`ctx`](CapturedIo::ctx), or use
+/// [`AutoWasi::detect_captured`](crate::AutoWasi::detect_captured). Clones share the buffers.
+///
+/// The buffers grow as the guest writes, up to the limit of
+/// [`with_limit`](CapturedIo::with_limit) if any. Writes past the limit fail, the guest's
+/// `fd_write` returning an errno, and [`check`](CapturedIo::check) reports the overflow.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # use wasmtime::*;
+/// # fn test(wasm: &[u8]) -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let captured = CapturedIo::with_limit(1024 * 1024);
+/// let wasi = AutoWasi::detect(&store, captured.ctx()?, wasm)?;
+/// // Instantiate and run the module...
+/// captured.check()?;
+/// println!("{}", captured.stdout_lossy());
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Default)]
+pub struct CapturedIo {
+    stdout: Arc<RwLock<Capture>>,
+    stderr: Arc<RwLock<Capture>>,
+}
+
+impl CapturedIo {
+    /// Creates empty buffers without a size limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates empty buffers that each hold at most `limit` bytes.
+    pub fn with_limit(limit: usize) -> Self {
+        let capture = || {
+            Arc::new(RwLock::new(Capture {
+                limit: Some(limit),
+                ..Capture::default()
+            }))
+        };
+        Self {
+            stdout: capture(),
+            stderr: capture(),
+        }
+    }
+
+    /// Builds a context writing stdout and stderr to the buffers, with an empty stdin.
+    ///
+    /// Use [`AutoWasiBuilder::capture_stdio`](crate::AutoWasiBuilder::capture_stdio) to configure
+    /// the rest of the context.
+    pub fn ctx(&self) -> Result<WasiCtx, WasiDetectError> {
+        let mut builder = WasiCtxBuilder::new();
+        self.capture_stdio(&mut builder);
+        Ok(builder.build()?)
+    }
+
+    /// Connects the builder's stdout and stderr to the buffers.
+    pub(crate) fn capture_stdio(&self, builder: &mut WasiCtxBuilder) {
+        builder
+            .stdout(WritePipe::from_shared(self.stdout.clone()))
+            .stderr(WritePipe::from_shared(self.stderr.clone()));
+    }
+
+    /// Returns what the guest wrote to stdout.
+    pub fn stdout(&self) -> Vec<u8> {
+        self.stdout.read().unwrap().data.clone()
+    }
+
+    /// Returns what the guest wrote to stderr.
+    pub fn stderr(&self) -> Vec<u8> {
+        self.stderr.read().unwrap().data.clone()
+    }
+
+    /// Returns what the guest wrote to stdout, replacing invalid UTF-8 with `U+FFFD`.
+    pub fn stdout_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stdout.read().unwrap().data).into_owned()
+    }
+
+    /// Returns what the guest wrote to stderr, replacing invalid UTF-8 with `U+FFFD`.
+    pub fn stderr_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.stderr.read().unwrap().data).into_owned()
+    }
+
+    /// Returns what the guest wrote to stdout, emptying the buffer.
+    pub fn take_stdout(&self) -> Vec<u8> {
+        self.stdout.write().unwrap().take()
+    }
+
+    /// Returns what the guest wrote to stderr, emptying the buffer.
+    pub fn take_stderr(&self) -> Vec<u8> {
+        self.stderr.write().unwrap().take()
+    }
+
+    /// Fails with
+    /// [`WasiDetectError::CaptureLimitExceeded`](crate::WasiDetectError::CaptureLimitExceeded) if
+    /// the guest wrote more than a buffer holds since it was last emptied, stdout first.
+    pub fn check(&self) -> Result<(), WasiDetectError> {
+        for (stream, capture) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            let capture = capture.read().unwrap();
+            if let (true, Some(limit)) = (capture.overflowed, capture.limit) {
+                return Err(WasiDetectError::CaptureLimitExceeded { stream, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Only prints how many bytes were captured, not the output.
+impl fmt::Debug for CapturedIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CapturedIo")
+            .field("stdout_len", &self.stdout.read().unwrap().data.len())
+            .field("stderr_len", &self.stderr.read().unwrap().data.len())
+            .finish()
+    }
+}
+
+/// A buffer a guest's output stream is written to.
+#[derive(Debug, Default)]
+struct Capture {
+    data: Vec<u8>,
+    limit: Option<usize>,
+    overflowed: bool,
+}
+
+impl Capture {
+    fn take(&mut self) -> Vec<u8> {
+        self.overflowed = false;
+        std::mem::take(&mut self.data)
+    }
+}
+
+impl Write for Capture {
+    /// Writes as many bytes as fit in the limit, failing once none do.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = match self.limit {
+            Some(limit) => buf.len().min(limit.saturating_sub(self.data.len())),
+            None => buf.len(),
+        };
+        if len < buf.len() {
+            self.overflowed = true;
+            if len == 0 {
+                return Err(io::Error::other("captured output exceeds the limit"));
+            }
+        }
+        self.data.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An [`AutoWasi`](crate::AutoWasi) whose stdout and stderr are written to in-memory buffers.
+///
+/// Created by [`AutoWasi::with_stdio_capture`](crate::AutoWasi::with_stdio_capture).
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # use wasmtime::*;
+/// # fn test(wasm: &[u8]) -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let captured = AutoWasi::with_stdio_capture(&store, wasm)?;
+/// let mut linker = Linker::new(&store);
+/// captured.wasi().add_to_linker(&mut linker)?;
+/// // Instantiate and run the module with the linker...
+/// let stdout = String::from_utf8(captured.take_stdout())?;
+/// # Ok(()) }
+/// ```
+pub struct AutoWasiWithCapture {
+    wasi: AutoWasi,
+    io: CapturedIo,
+}
+
+impl AutoWasiWithCapture {
+    pub(crate) fn new(wasi: AutoWasi, io: CapturedIo) -> Self {
+        Self { wasi, io }
+    }
+
+    /// Returns the instance writing to the buffers.
+    pub fn wasi(&self) -> &AutoWasi {
+        &self.wasi
+    }
+
+    /// Returns the buffers the instance writes to.
+    pub fn io(&self) -> &CapturedIo {
+        &self.io
+    }
+
+    /// Returns what the module wrote to stdout since the last call, emptying the buffer.
+    pub fn take_stdout(&self) -> Vec<u8> {
+        self.io.take_stdout()
+    }
+
+    /// Returns what the module wrote to stderr since the last call, emptying the buffer.
+    pub fn take_stderr(&self) -> Vec<u8> {
+        self.io.take_stderr()
+    }
+}
+
+/// Only prints the instance, not the captured output.
+impl fmt::Debug for AutoWasiWithCapture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AutoWasiWithCapture")
+            .field("wasi", &self.wasi)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,141 @@
+//! Replacing the clocks and entropy WASI functions read.
+
+use std::cell::Cell;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wasmtime::{Caller, Extern, Func, Store, Trap};
+
+/// The `success` errno, which is the same in both snapshots.
+const SUCCESS: i32 = 0;
+/// The `fault` errno, which is the same in both snapshots.
+const FAULT: i32 = 21;
+/// The `inval` errno, which is the same in both snapshots.
+const INVAL: i32 = 28;
+/// The number of clocks, `realtime`, `monotonic`, `process_cputime_id` and `thread_cputime_id`.
+const CLOCKS: i32 = 4;
+/// The id of the `realtime` clock.
+const REALTIME: i32 = 0;
+
+/// Returns a `clock_time_get` function answering the clock with the id with the time `clock_fn`
+/// returns for it.
+///
+/// Both snapshots define the function with the same signature and layouts. Calling the original
+/// implementation for the other clocks isn't possible, host functions called from the host don't
+/// get the module's memory to write the timestamp to.
+pub(crate) fn clock_time_get(store: &Store, clock_fn: impl Fn(i32) -> u64 + 'static) -> Func {
+    Func::wrap(
+        store,
+        move |caller: Caller<'_>, id: i32, _precision: i64, time: i32| -> Result<i32, Trap> {
+            if !(0..CLOCKS).contains(&id) {
+                return Ok(INVAL);
+            }
+            with_guest_bytes(&caller, time, 8, |bytes| {
+                bytes.copy_from_slice(&clock_fn(id).to_le_bytes())
+            })
+        },
+    )
+}
+
+/// Returns a `random_get` function filling the buffer with the bytes `fill` writes.
+pub(crate) fn random_get(store: &Store, fill: impl Fn(&mut [u8]) + 'static) -> Func {
+    Func::wrap(
+        store,
+        move |caller: Caller<'_>, buf: i32, len: i32| -> Result<i32, Trap> {
+            with_guest_bytes(&caller, buf, len as u32 as usize, &fill)
+        },
+    )
+}
+
+/// Calls `f` with the bytes of the caller's memory at the offset, returning the errno.
+fn with_guest_bytes(
+    caller: &Caller<'_>,
+    offset: i32,
+    len: usize,
+    f: impl FnOnce(&mut [u8]),
+) -> Result<i32, Trap> {
+    let memory = match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => memory,
+        _ => return Err(Trap::new("missing required memory export")),
+    };
+
+    let start = offset as u32 as usize;
+    // Safety: the memory isn't borrowed elsewhere while the function runs.
+    let data = unsafe { memory.data_unchecked_mut() };
+    match data.get_mut(start..start.saturating_add(len)) {
+        Some(bytes) => {
+            f(bytes);
+            Ok(SUCCESS)
+        }
+        None => Ok(FAULT),
+    }
+}
+
+/// The options of [`AutoWasi::deterministic`](crate::AutoWasi::deterministic).
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # use std::time::{Duration, UNIX_EPOCH};
+/// let config = DeterministicConfig {
+///     start_time: UNIX_EPOCH + Duration::from_secs(1_609_459_200),
+///     seed: 42,
+///     ..DeterministicConfig::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeterministicConfig {
+    /// The time the `realtime` clock starts at, [`UNIX_EPOCH`] by default.
+    pub start_time: SystemTime,
+    /// The seed of the bytes `random_get` returns, 0 by default.
+    pub seed: u64,
+    /// How much the clocks advance each time the guest reads one, 1 millisecond by default.
+    pub tick: Duration,
+}
+
+impl Default for DeterministicConfig {
+    fn default() -> Self {
+        Self {
+            start_time: UNIX_EPOCH,
+            seed: 0,
+            tick: Duration::from_millis(1),
+        }
+    }
+}
+
+impl DeterministicConfig {
+    /// Returns the `clock_time_get` and `random_get` functions of the config.
+    pub(crate) fn functions(&self, store: &Store) -> Vec<(&'static str, Func)> {
+        let start = self
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let tick = self.tick.as_nanos() as u64;
+        let elapsed = Cell::new(0u64);
+        let clock = clock_time_get(store, move |id| {
+            elapsed.set(elapsed.get().wrapping_add(tick));
+            match id {
+                REALTIME => start.wrapping_add(elapsed.get()),
+                _ => elapsed.get(),
+            }
+        });
+
+        let state = Cell::new(self.seed);
+        let random = random_get(store, move |bytes| {
+            for chunk in bytes.chunks_mut(8) {
+                let value = splitmix64(&state).to_le_bytes();
+                chunk.copy_from_slice(&value[..chunk.len()]);
+            }
+        });
+
+        vec![("clock_time_get", clock), ("random_get", random)]
+    }
+}
+
+/// Advances the state of a SplitMix64 generator, returning its next value.
+fn splitmix64(state: &Cell<u64>) -> u64 {
+    let next = state.get().wrapping_add(0x9e37_79b9_7f4a_7c15);
+    state.set(next);
+    let mut z = next;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
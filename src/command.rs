@@ -0,0 +1,165 @@
+use crate::{exports::Exports, text_to_binary, WasiDetectError, WasiVersion};
+use std::fmt;
+use wasmtime::{Func, Instance, Trap, Val};
+
+/// The exit status of a command module run with
+/// [`AutoWasi::run_command`](crate::AutoWasi::run_command).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExitStatus {
+    code: i32,
+}
+
+impl ExitStatus {
+    pub(crate) fn new(code: i32) -> Self {
+        Self { code }
+    }
+
+    /// Returns the code the module exited with, 0 if `_start` returned.
+    ///
+    /// Like [`std::process::ExitStatus::code`] this is optional, though a command module always
+    /// exits with a code as traps are returned as errors instead.
+    pub fn code(&self) -> Option<i32> {
+        Some(self.code)
+    }
+
+    /// Returns whether the module exited with code 0.
+    pub fn success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+/// Formats the status like [`std::process::ExitStatus`], such as `exit status: 1`.
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exit status: {}", self.code)
+    }
+}
+
+/// Whether a module follows the WASI command or reactor conventions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleKind {
+    /// A module run once through its `_start` function, see
+    /// [`AutoWasi::run_command`](crate::AutoWasi::run_command).
+    Command,
+    /// A module initialized through its `_initialize` function, if it exports one, whose other
+    /// exports are then called any number of times, see
+    /// [`AutoWasi::instantiate_reactor`](crate::AutoWasi::instantiate_reactor).
+    Reactor,
+}
+
+impl ModuleKind {
+    /// Detects the kind of the module from its exports.
+    ///
+    /// Modules exporting `_start` but not `_initialize` are commands, every other module is a
+    /// reactor. Only the module's sections up until the end of its export section are parsed.
+    pub fn detect<T: AsRef<[u8]>>(binary: T) -> Result<Self, WasiDetectError> {
+        let binary = text_to_binary(binary.as_ref())?;
+        let exports = Exports::parse(&binary, usize::MAX)?.unwrap_or_default();
+        if exports.start && !exports.initialize {
+            Ok(Self::Command)
+        } else {
+            Ok(Self::Reactor)
+        }
+    }
+}
+
+/// Formats the kind in lowercase, such as `reactor`.
+impl fmt::Display for ModuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command => write!(f, "command"),
+            Self::Reactor => write!(f, "reactor"),
+        }
+    }
+}
+
+/// An instantiated reactor module whose `_initialize` function has been called.
+///
+/// Created by [`AutoWasi::instantiate_reactor`](crate::AutoWasi::instantiate_reactor).
+///
+/// Exported functions can be called with [`call`](Reactor::call), or with the typed accessors
+/// of [`Func`](wasmtime::Func) such as [`get0`](wasmtime::Func::get0):
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # use wasmtime::*;
+/// # use wasmtime_wasi::*;
+/// # fn test() -> anyhow::Result<()> {
+/// let wasm = wat::parse_str(
+///     r#"(module
+///         (global $answer (mut i32) (i32.const 0))
+///         (func (export "_initialize") (global.set $answer (i32.const 42)))
+///         (func (export "answer") (result i32) (global.get $answer))
+///     )"#,
+/// )?;
+/// let store = Store::default();
+/// let reactor = AutoWasi::instantiate_reactor(&store, WasiCtx::new(std::env::args())?, &wasm)?;
+/// let answer = reactor.get_func("answer").unwrap().get0::<i32>()?;
+/// assert_eq!(answer()?, 42);
+/// # Ok(()) }
+/// ```
+pub struct Reactor {
+    instance: Instance,
+    version: WasiVersion,
+}
+
+impl Reactor {
+    pub(crate) fn new(instance: Instance, version: WasiVersion) -> Self {
+        Self { instance, version }
+    }
+
+    /// Returns the instance of the module.
+    pub fn instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    /// Returns the WASI version linked to the module.
+    pub fn version(&self) -> WasiVersion {
+        self.version
+    }
+
+    /// Returns the function the module exports under the name, if any.
+    pub fn get_func(&self, name: &str) -> Option<Func> {
+        self.instance.get_func(name)
+    }
+
+    /// Calls the function the module exports under the name with the params, returning its
+    /// results.
+    ///
+    /// Fails with [`WasiDetectError::MissingFunction`](crate::WasiDetectError::MissingFunction) if
+    /// the module doesn't export a function with the name, and with
+    /// [`WasiDetectError::Trap`](crate::WasiDetectError::Trap) if the params don't match its
+    /// signature or it traps.
+    pub fn call(&self, name: &str, params: &[Val]) -> Result<Box<[Val]>, WasiDetectError> {
+        let func = self
+            .get_func(name)
+            .ok_or_else(|| missing_function(&self.instance, name))?;
+        func.call(params)
+            .map_err(|error| WasiDetectError::Trap(trap(error)))
+    }
+}
+
+/// Only prints the version, the instance isn't `Debug`.
+impl fmt::Debug for Reactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reactor")
+            .field("version", &self.version)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the trap an error returned by [`Func::call`](wasmtime::Func::call) carries.
+pub(crate) fn trap(error: anyhow::Error) -> Trap {
+    // `Trap::from` wraps traps carried by an `anyhow::Error` in another trap.
+    error.downcast::<Trap>().unwrap_or_else(Trap::from)
+}
+
+pub(crate) fn missing_function(instance: &Instance, name: &str) -> WasiDetectError {
+    WasiDetectError::MissingFunction {
+        name: name.to_string(),
+        exports: instance
+            .exports()
+            .map(|export| export.name().to_string())
+            .collect(),
+    }
+}
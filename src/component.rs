@@ -0,0 +1,53 @@
+use wasmparser::{BinaryReader, BinaryReaderError};
+
+const MAGIC: &[u8] = b"\0asm";
+/// The layer field of the header, 0 for core modules and 1 for components.
+const COMPONENT_LAYER: &[u8] = &[1, 0];
+
+const CORE_MODULE_SECTION: u8 = 1;
+const COMPONENT_SECTION: u8 = 4;
+
+/// Returns whether the binary has a component header rather than a core module one.
+pub(crate) fn is_component(binary: &[u8]) -> bool {
+    binary.len() >= 8 && &binary[..4] == MAGIC && &binary[6..8] == COMPONENT_LAYER
+}
+
+/// Collects the core modules embedded in the component, including those of nested components, in
+/// the order they appear.
+pub(crate) fn core_modules(binary: &[u8]) -> Result<Vec<&[u8]>, BinaryReaderError> {
+    let mut modules = Vec::new();
+    for_each_core_module(binary, &mut |module| modules.push(module))?;
+    Ok(modules)
+}
+
+/// Calls `f` with each core module embedded in the component, including those of nested
+/// components, in the order they appear.
+pub(crate) fn for_each_core_module<'a>(
+    binary: &'a [u8],
+    f: &mut impl FnMut(&'a [u8]),
+) -> Result<(), BinaryReaderError> {
+    visit_core_modules(BinaryReader::new(binary), f)
+}
+
+fn visit_core_modules<'a>(
+    mut reader: BinaryReader<'a>,
+    f: &mut impl FnMut(&'a [u8]),
+) -> Result<(), BinaryReaderError> {
+    reader.read_bytes(8)?;
+
+    while !reader.eof() {
+        let id = reader.read_u8()? as u8;
+        let size = reader.read_var_u32()? as usize;
+        let offset = reader.original_position();
+        let contents = reader.read_bytes(size)?;
+        match id {
+            CORE_MODULE_SECTION => f(contents),
+            COMPONENT_SECTION => {
+                visit_core_modules(BinaryReader::new_with_offset(contents, offset), f)?
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
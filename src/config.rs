@@ -0,0 +1,73 @@
+use crate::{DetectLimits, WasiVersion};
+
+/// The options of [`WasiVersion::detect_with_config`](crate::WasiVersion::detect_with_config).
+///
+/// The [default](DetectionConfig::default) config detects like
+/// [`WasiVersion::detect`](crate::WasiVersion::detect), change its fields to get the behavior of
+/// the other detection functions or combine them.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # fn test() -> anyhow::Result<()> {
+/// # let wasm = wat::parse_str("(module (import \"wasi_unstable\" \"proc_exit\" (func (param i32))))")?;
+/// let config = DetectionConfig {
+///     strict: true,
+///     reject_unknown_functions: true,
+///     ..DetectionConfig::default()
+/// };
+/// let version = WasiVersion::detect_with_config(wasm, &config)?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectionConfig {
+    /// Whether to fail with [`WasiDetectError::NoWasiImports`](crate::WasiDetectError::NoWasiImports)
+    /// for binaries without WASI imports instead of using the fallback, `false` by default.
+    pub strict: bool,
+    /// The version of binaries without WASI imports whose version isn't guessed from their
+    /// exports, [`WasiVersion::LATEST`] by default.
+    ///
+    /// The guess takes precedence, disable [`export_heuristics`](Self::export_heuristics) to
+    /// detect every binary without WASI imports as the fallback.
+    pub fallback: WasiVersion,
+    /// Whether to guess the version of binaries without WASI imports from their exports before
+    /// using the fallback, `true` by default.
+    ///
+    /// Modules exporting `memory` and `_start` or `_initialize` follow the preview1 command or
+    /// reactor ABI and are guessed as [`WasiVersion::Snapshot1`].
+    pub export_heuristics: bool,
+    /// Whether to fail with
+    /// [`WasiDetectError::UnknownFunctions`](crate::WasiDetectError::UnknownFunctions) for
+    /// binaries importing functions the detected version doesn't define, `false` by default.
+    pub reject_unknown_functions: bool,
+    /// What to do with binaries that only import from modules named like an unknown WASI version.
+    pub unknown_namespaces: UnknownNamespaceAction,
+    /// The limits detection is done within.
+    pub limits: DetectLimits,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            fallback: WasiVersion::LATEST,
+            export_heuristics: true,
+            reject_unknown_functions: false,
+            unknown_namespaces: UnknownNamespaceAction::default(),
+            limits: DetectLimits::default(),
+        }
+    }
+}
+
+/// What detection does with a binary importing no known WASI version, but importing from a module
+/// named like one, such as `wasi_snapshot_preview2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum UnknownNamespaceAction {
+    /// Fail with
+    /// [`WasiDetectError::UnknownWasiModule`](crate::WasiDetectError::UnknownWasiModule), the
+    /// default.
+    #[default]
+    Error,
+    /// Detect the binary as if it didn't import any WASI version.
+    Ignore,
+}
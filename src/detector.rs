@@ -0,0 +1,186 @@
+use crate::{component, DetectLimits, WasiDetectError, WasiImports, WasiVersion};
+use std::io;
+use wasmparser::{Chunk, Parser};
+
+/// Detects the WASI version of a binary fed chunk by chunk, such as while it's downloaded.
+///
+/// Detection ends at the binary's import section like
+/// [`WasiVersion::detect_from_reader`](crate::WasiVersion::detect_from_reader), so the download
+/// can be cancelled as soon as [`feed`](Self::feed) returns a result. Only the chunks of the
+/// section being parsed are buffered, components are buffered whole to find their modules. Text
+/// format modules aren't converted and fail to parse.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # fn test() -> anyhow::Result<()> {
+/// # let wasm = wat::parse_str("(module (import \"wasi_unstable\" \"proc_exit\" (func (param i32))))")?;
+/// let mut detector = WasiVersionDetector::new();
+/// for chunk in wasm.chunks(8) {
+///     if let Some(version) = detector.feed(chunk) {
+///         assert_eq!(version?, WasiVersion::Snapshot0);
+///         break;
+///     }
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Default)]
+pub struct WasiVersionDetector {
+    parser: Parser,
+    imports: WasiImports,
+    /// The fed bytes that haven't been parsed yet, or the whole component.
+    buf: Vec<u8>,
+    /// The number of bytes fed.
+    offset: usize,
+    component: bool,
+    state: State,
+}
+
+#[derive(Default)]
+enum State {
+    #[default]
+    Detecting,
+    Detected(WasiVersion),
+    Failed(WasiDetectError),
+}
+
+impl WasiVersionDetector {
+    /// Creates a detector that hasn't been fed anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a detector detecting within the provided limits.
+    pub fn with_limits(limits: DetectLimits) -> Self {
+        let mut detector = Self::default();
+        detector.imports.limits = limits;
+        detector
+    }
+
+    /// Parses the chunk following the ones fed before, returning the detected version as soon as
+    /// it's known, or `None` if more data is needed.
+    ///
+    /// Once a version is detected, or detection failed, the result is returned again for every
+    /// later chunk, without parsing them.
+    pub fn feed(&mut self, chunk: &[u8]) -> Option<Result<WasiVersion, WasiDetectError>> {
+        match &self.state {
+            State::Detecting => {}
+            State::Detected(version) => return Some(Ok(*version)),
+            State::Failed(error) => return Some(Err(repeat(error))),
+        }
+
+        self.offset += chunk.len();
+        self.buf.extend_from_slice(chunk);
+        let result = self.advance(false)?;
+        Some(self.finish(result))
+    }
+
+    /// Ends the binary, returning the detected version.
+    ///
+    /// Binaries without WASI imports default to the latest version. Fails with
+    /// [`WasiDetectError::Truncated`](crate::WasiDetectError::Truncated) if the binary ended
+    /// before its import section did, and with the error again if detection already failed.
+    pub fn finalize(&mut self) -> Result<WasiVersion, WasiDetectError> {
+        match &self.state {
+            State::Detecting => {}
+            State::Detected(version) => return Ok(*version),
+            State::Failed(error) => return Err(repeat(error)),
+        }
+
+        let result = match self.advance(true) {
+            Some(result) => result,
+            // The parser doesn't ask for more data at the end of the binary.
+            None => Err(WasiDetectError::Truncated {
+                offset: self.offset,
+            }),
+        };
+        self.finish(result)
+    }
+
+    /// Parses the buffered data, returning `None` if more is needed.
+    fn advance(&mut self, eof: bool) -> Option<Result<WasiVersion, WasiDetectError>> {
+        let limits = self.imports.limits;
+        if self.component {
+            if let Err(error) = DetectLimits::check("max_bytes", limits.max_bytes, self.buf.len()) {
+                return Some(Err(error));
+            }
+            if !eof {
+                return None;
+            }
+            let result = WasiImports::parse_component(&self.buf, &limits)
+                .and_then(|imports| imports.detected_version());
+            return Some(result.map(Option::unwrap_or_default));
+        }
+
+        loop {
+            let (payload, consumed) = match self.parser.parse(&self.buf, eof) {
+                Ok(Chunk::NeedMoreData(hint)) => {
+                    let end = self.offset + hint as usize;
+                    if let Err(error) = DetectLimits::check("max_bytes", limits.max_bytes, end) {
+                        return Some(Err(error));
+                    }
+                    return None;
+                }
+                Ok(Chunk::Parsed { consumed, payload }) => (payload, consumed),
+                // Components are buffered whole to find their modules.
+                Err(_) if component::is_component(&self.buf) => {
+                    self.component = true;
+                    return self.advance(eof);
+                }
+                // The parser only fails at the end of the binary after asking for more data.
+                Err(_) if eof => {
+                    return Some(Err(WasiDetectError::Truncated {
+                        offset: self.offset,
+                    }))
+                }
+                Err(error) => return Some(Err(error.into())),
+            };
+
+            match self.imports.visit(payload) {
+                Ok(true) => {}
+                Ok(false) => {
+                    let version = self.imports.detected_version();
+                    return Some(version.map(Option::unwrap_or_default));
+                }
+                Err(error) => return Some(Err(error)),
+            }
+            self.buf.drain(..consumed);
+        }
+    }
+
+    /// Records the result, freeing the buffered data.
+    fn finish(
+        &mut self,
+        result: Result<WasiVersion, WasiDetectError>,
+    ) -> Result<WasiVersion, WasiDetectError> {
+        self.buf = Vec::new();
+        match result {
+            Ok(version) => {
+                self.state = State::Detected(version);
+                Ok(version)
+            }
+            Err(error) => {
+                let repeated = repeat(&error);
+                self.state = State::Failed(error);
+                Err(repeated)
+            }
+        }
+    }
+}
+
+/// Returns a copy of a detection error, to return it again for the chunks fed after it.
+fn repeat(error: &WasiDetectError) -> WasiDetectError {
+    match error {
+        WasiDetectError::Parse(error) => WasiDetectError::Parse(error.clone()),
+        WasiDetectError::Component => WasiDetectError::Component,
+        WasiDetectError::Truncated { offset } => WasiDetectError::Truncated { offset: *offset },
+        WasiDetectError::LimitExceeded { limit, max } => {
+            WasiDetectError::LimitExceeded { limit, max: *max }
+        }
+        WasiDetectError::MixedVersions(error) => WasiDetectError::MixedVersions(error.clone()),
+        WasiDetectError::UnknownWasiModule { module } => WasiDetectError::UnknownWasiModule {
+            module: module.clone(),
+        },
+        // Detection doesn't fail with the others, some of which hold errors that can't be cloned.
+        error => WasiDetectError::Io(io::Error::other(error.to_string())),
+    }
+}
@@ -0,0 +1,612 @@
+use crate::report::FuncTypeText;
+use crate::{ImportKind, MissingExport, WasiVersion, WasiVersionRequirement};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use wasi_common::WasiCtxBuilderError;
+use wasmparser::BinaryReaderError;
+use wasmtime::{FuncType, Trap};
+
+/// An error that occurred while detecting the WASI version of a binary, or while creating an
+/// [`AutoWasi`](crate::AutoWasi) for it and linking it.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WasiDetectError {
+    /// The binary isn't valid WebAssembly.
+    ///
+    /// The error's [`offset`](wasmparser::BinaryReaderError::offset) is the position in the
+    /// binary parsing failed at.
+    Parse(BinaryReaderError),
+    /// The text format module isn't valid.
+    #[cfg(feature = "wat")]
+    Wat(wat::Error),
+    /// The precompiled module couldn't be deserialized.
+    Deserialize(anyhow::Error),
+    /// The module couldn't be compiled.
+    Compile(anyhow::Error),
+    /// The binary is a component without any WASI imports in its core modules, see
+    /// [`WasiVersion::detect_in_component`](crate::WasiVersion::detect_in_component).
+    Component,
+    /// The binary couldn't be read.
+    Io(io::Error),
+    /// The range of an embedded binary doesn't fit in the data containing it.
+    InvalidRange {
+        /// The offset of the binary in the data.
+        offset: usize,
+        /// The length of the binary.
+        length: usize,
+        /// The length of the data.
+        len: usize,
+    },
+    /// The binary ended before its import section did.
+    Truncated {
+        /// The length of the binary.
+        offset: usize,
+    },
+    /// The binary exceeds one of the [`DetectLimits`](crate::DetectLimits) detection is done
+    /// within.
+    LimitExceeded {
+        /// The name of the exceeded limit, such as `max_imports`.
+        limit: &'static str,
+        /// The value of the limit.
+        max: usize,
+    },
+    /// The binary imports from more than one WASI version.
+    MixedVersions(MixedVersionsError),
+    /// The binary doesn't import any WASI version.
+    NoWasiImports {
+        /// The namespaces the binary imports from instead, in the order they first appear.
+        namespaces: Vec<String>,
+    },
+    /// The binary doesn't import any WASI version this release knows, but imports from a module
+    /// named like one, such as a newer snapshot.
+    UnknownWasiModule {
+        /// The name of the first such module, such as `wasi_snapshot_preview2`.
+        module: String,
+    },
+    /// The binary imports functions the detected version doesn't define.
+    UnknownFunctions {
+        /// The detected version.
+        version: WasiVersion,
+        /// The names of the unknown functions, in the order they first appear.
+        names: Vec<String>,
+    },
+    /// The binary uses another WASI version than the one provided.
+    VersionMismatch(WasiVersionMismatchError),
+    /// The binary imports functions that can't be rewritten to import another WASI version, see
+    /// [`rewrite::upgrade_to_preview1`](crate::rewrite::upgrade_to_preview1).
+    NotUpgradable {
+        /// The names of the functions, in the order they're first imported.
+        functions: Vec<String>,
+    },
+    /// The binary uses a WASI version that doesn't satisfy a
+    /// [`WasiVersionRequirement`](crate::WasiVersionRequirement).
+    UnsatisfiedRequirement(RequirementError),
+    /// The version can be detected but an [`AutoWasi`](crate::AutoWasi) can't be created for it.
+    UnsupportedVersion(WasiVersion),
+    /// The [`WasiCtx`](wasi_common::WasiCtx) couldn't be built.
+    Ctx(WasiCtxBuilderError),
+    /// A directory couldn't be opened to be preopened for the module.
+    Preopen {
+        /// The host path of the directory.
+        path: PathBuf,
+        /// The reason the directory couldn't be opened.
+        error: io::Error,
+    },
+    /// Two builder options that can't be used together were both set.
+    ConflictingOptions {
+        /// The name of the first option.
+        first: &'static str,
+        /// The name of the second option.
+        second: &'static str,
+    },
+    /// The module imports WASI functions the [`AutoWasi`](crate::AutoWasi) doesn't provide.
+    MissingExports(MissingExportsError),
+    /// An import of the module can't be stubbed as it isn't a function.
+    Unstubbable {
+        /// The name of the module imported from.
+        module: String,
+        /// The name of the imported field.
+        name: String,
+        /// The kind of the imported item.
+        kind: ImportKind,
+    },
+    /// The allowlist of functions to link is empty or names unknown functions.
+    InvalidAllowlist {
+        /// The names that aren't functions of the WASI version, empty if the allowlist is.
+        unknown: Vec<String>,
+    },
+    /// The [`Linker`](wasmtime::Linker) already defines WASI functions, see
+    /// [`Conflict::Error`](crate::Conflict::Error).
+    Conflicts {
+        /// The module the functions are defined in.
+        module: String,
+        /// The names of the functions, sorted.
+        names: Vec<String>,
+    },
+    /// The [`Linker`](wasmtime::Linker) already defines a WASI function with another type, see
+    /// [`AutoWasi::add_to_linker_checked`](crate::AutoWasi::add_to_linker_checked).
+    LinkerMismatch {
+        /// The module the function is defined in.
+        module: String,
+        /// The name of the function.
+        name: String,
+        /// The signature WASI defines for the function.
+        expected: FuncType,
+        /// The signature of the linker's definition.
+        found: FuncType,
+    },
+    /// A function to override isn't a function of the WASI version, see
+    /// [`AutoWasi::with_overrides`](crate::AutoWasi::with_overrides).
+    UnknownOverride {
+        /// The name of the function.
+        name: String,
+    },
+    /// A function to override has another type than the function of the WASI version, see
+    /// [`AutoWasi::with_overrides`](crate::AutoWasi::with_overrides).
+    OverrideMismatch {
+        /// The name of the function.
+        name: String,
+        /// The signature WASI defines for the function.
+        expected: FuncType,
+        /// The signature of the override.
+        found: FuncType,
+    },
+    /// A guest wrote more than a [`CapturedIo`](crate::CapturedIo) buffer holds.
+    CaptureLimitExceeded {
+        /// The stream written to, `stdout` or `stderr`.
+        stream: &'static str,
+        /// The maximum number of bytes the buffer holds.
+        limit: usize,
+    },
+    /// The module name to add WASI functions to a [`Linker`](wasmtime::Linker) under is empty.
+    EmptyModuleName,
+    /// The [`Linker`](wasmtime::Linker) rejected a definition.
+    Link(anyhow::Error),
+    /// The module couldn't be instantiated, such as when it imports functions that weren't linked
+    /// or its start function traps.
+    Instantiate(anyhow::Error),
+    /// The module doesn't export a function it's expected to, such as the `_start` function of
+    /// command modules.
+    MissingFunction {
+        /// The name of the function.
+        name: String,
+        /// The names the module exports instead, in the order they appear.
+        exports: Vec<String>,
+    },
+    /// The module is a [command](crate::ModuleKind::Command) rather than a reactor, run it with
+    /// [`AutoWasi::run_command`](crate::AutoWasi::run_command) instead.
+    CommandModule,
+    /// The module trapped, such as a command module trapping rather than exiting with
+    /// [`proc_exit`](wasmtime::Trap::i32_exit_status).
+    Trap(Trap),
+    /// Detection failed for the binary at the provided path.
+    Path {
+        /// The path of the binary.
+        path: PathBuf,
+        /// The reason detection failed.
+        error: Box<WasiDetectError>,
+    },
+}
+
+impl fmt::Display for WasiDetectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(error) => write!(f, "failed to parse wasm binary: {}", error),
+            #[cfg(feature = "wat")]
+            Self::Wat(error) => write!(f, "failed to parse wasm text: {}", error),
+            Self::Deserialize(error) => {
+                write!(f, "failed to deserialize precompiled module: {}", error)
+            }
+            Self::Compile(error) => write!(f, "failed to compile module: {}", error),
+            Self::Component => write!(
+                f,
+                "binary is a component whose core modules don't import WASI, use \
+                 `WasiVersion::detect_in_component` to inspect them"
+            ),
+            Self::Io(error) => write!(f, "failed to read wasm binary: {}", error),
+            Self::InvalidRange {
+                offset,
+                length,
+                len,
+            } => write!(
+                f,
+                "{} bytes at offset {} are out of bounds of {} bytes of data",
+                length, offset, len
+            ),
+            Self::Truncated { offset } => {
+                write!(f, "wasm binary ends unexpectedly after {} bytes", offset)
+            }
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "binary exceeds detection limit `{}` of {}", limit, max)
+            }
+            Self::MixedVersions(error) => error.fmt(f),
+            Self::NoWasiImports { namespaces } if namespaces.is_empty() => {
+                write!(f, "binary doesn't import anything")
+            }
+            Self::NoWasiImports { namespaces } => write!(
+                f,
+                "binary doesn't import any WASI version, only: {}",
+                namespaces.join(", ")
+            ),
+            Self::UnknownWasiModule { module } => write!(
+                f,
+                "binary imports from unknown WASI module `{}` instead of a supported WASI version",
+                module
+            ),
+            Self::UnknownFunctions { version, names } => write!(
+                f,
+                "binary imports functions unknown to {}: {}",
+                version.module_name(),
+                names.join(", ")
+            ),
+            Self::UnsupportedVersion(version @ WasiVersion::Preview2) => write!(
+                f,
+                "linking {} is not supported, wasmtime-wasi doesn't implement its component-model \
+                 interfaces",
+                version
+            ),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "linking {} is not supported", version)
+            }
+            Self::Ctx(error) => write!(f, "failed to build WASI context: {}", error),
+            Self::Preopen { path, error } => write!(
+                f,
+                "failed to open directory {} to preopen: {}",
+                path.display(),
+                error
+            ),
+            Self::ConflictingOptions { first, second } => {
+                write!(f, "`{}` can't be combined with `{}`", first, second)
+            }
+            Self::VersionMismatch(error) => error.fmt(f),
+            Self::UnsatisfiedRequirement(error) => error.fmt(f),
+            Self::NotUpgradable { functions } => write!(
+                f,
+                "binary imports WASI functions that can't be upgraded: {}",
+                functions.join(", ")
+            ),
+            Self::MissingExports(error) => error.fmt(f),
+            Self::Unstubbable { module, name, kind } => {
+                write!(f, "can't stub {} import `{}.{}`", kind, module, name)
+            }
+            Self::InvalidAllowlist { unknown } if unknown.is_empty() => {
+                write!(f, "allowlist of WASI functions is empty")
+            }
+            Self::InvalidAllowlist { unknown } => write!(
+                f,
+                "allowlist names unknown WASI functions: {}",
+                unknown.join(", ")
+            ),
+            Self::Conflicts { module, names } => write!(
+                f,
+                "linker already defines WASI functions of {}: {}",
+                module,
+                names.join(", ")
+            ),
+            Self::LinkerMismatch {
+                module,
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "linker defines WASI function `{}` of {} with type {}, expected {}",
+                name,
+                module,
+                FuncTypeText(found),
+                FuncTypeText(expected)
+            ),
+            Self::UnknownOverride { name } => {
+                write!(f, "can't override unknown WASI function `{}`", name)
+            }
+            Self::OverrideMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "override of WASI function `{}` has type {}, expected {}",
+                name,
+                FuncTypeText(found),
+                FuncTypeText(expected)
+            ),
+            Self::CaptureLimitExceeded { stream, limit } => write!(
+                f,
+                "guest wrote more than the {} bytes captured from {}",
+                limit, stream
+            ),
+            Self::EmptyModuleName => write!(f, "module name of WASI functions is empty"),
+            Self::Link(error) => write!(f, "failed to link WASI functions: {}", error),
+            Self::Instantiate(error) => write!(f, "failed to instantiate module: {}", error),
+            Self::MissingFunction { name, exports } if exports.is_empty() => write!(
+                f,
+                "module doesn't export anything, expected a `{}` function",
+                name
+            ),
+            Self::MissingFunction { name, exports } => write!(
+                f,
+                "module doesn't export a `{}` function, it exports: {}",
+                name,
+                exports.join(", ")
+            ),
+            Self::CommandModule => write!(
+                f,
+                "module is a command exporting `_start` without `_initialize`, not a reactor"
+            ),
+            Self::Trap(trap) => write!(f, "module trapped: {}", trap),
+            Self::Path { path, error } => write!(
+                f,
+                "failed to detect WASI version of {}: {}",
+                path.display(),
+                error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WasiDetectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(error) => Some(error),
+            #[cfg(feature = "wat")]
+            Self::Wat(error) => Some(error),
+            Self::Deserialize(error) | Self::Compile(error) => Some(error.as_ref()),
+            Self::Io(error) => Some(error),
+            Self::MixedVersions(error) => Some(error),
+            Self::Ctx(error) => Some(error),
+            Self::Preopen { error, .. } => Some(error),
+            Self::Component
+            | Self::InvalidRange { .. }
+            | Self::Truncated { .. }
+            | Self::LimitExceeded { .. }
+            | Self::NoWasiImports { .. }
+            | Self::UnknownWasiModule { .. }
+            | Self::UnknownFunctions { .. }
+            | Self::NotUpgradable { .. }
+            | Self::UnsupportedVersion(_)
+            | Self::ConflictingOptions { .. } => None,
+            Self::VersionMismatch(error) => Some(error),
+            Self::UnsatisfiedRequirement(error) => Some(error),
+            Self::MissingExports(error) => Some(error),
+            Self::Unstubbable { .. }
+            | Self::InvalidAllowlist { .. }
+            | Self::Conflicts { .. }
+            | Self::LinkerMismatch { .. }
+            | Self::UnknownOverride { .. }
+            | Self::OverrideMismatch { .. }
+            | Self::CaptureLimitExceeded { .. }
+            | Self::EmptyModuleName => None,
+            Self::Link(error) | Self::Instantiate(error) => Some(error.as_ref()),
+            Self::MissingFunction { .. } | Self::CommandModule => None,
+            Self::Trap(trap) => Some(trap),
+            Self::Path { error, .. } => Some(error),
+        }
+    }
+}
+
+impl From<BinaryReaderError> for WasiDetectError {
+    fn from(error: BinaryReaderError) -> Self {
+        Self::Parse(error)
+    }
+}
+
+impl From<WasiCtxBuilderError> for WasiDetectError {
+    fn from(error: WasiCtxBuilderError) -> Self {
+        Self::Ctx(error)
+    }
+}
+
+#[cfg(feature = "wat")]
+impl From<wat::Error> for WasiDetectError {
+    fn from(error: wat::Error) -> Self {
+        Self::Wat(error)
+    }
+}
+
+impl From<io::Error> for WasiDetectError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<MixedVersionsError> for WasiDetectError {
+    fn from(error: MixedVersionsError) -> Self {
+        Self::MixedVersions(error)
+    }
+}
+
+/// The error returned when a binary imports from more than one WASI version.
+///
+/// Such a binary can't be linked by a single [`AutoWasi`](crate::AutoWasi), as it only provides the
+/// imports of one version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MixedVersionsError {
+    /// The imported functions grouped by the version they were imported from, in the order the
+    /// versions first appear in the binary.
+    pub imports: Vec<(WasiVersion, Vec<String>)>,
+}
+
+impl fmt::Display for MixedVersionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "binary imports from multiple WASI versions:")?;
+        for (version, fields) in &self.imports {
+            write!(f, " {} ({})", version.module_name(), fields.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MixedVersionsError {}
+
+/// The error returned when a module imports WASI functions that an [`AutoWasi`](crate::AutoWasi)
+/// doesn't provide.
+///
+/// Returned by [`AutoWasi::add_to_linker_for_module`](crate::AutoWasi::add_to_linker_for_module)
+/// as [`WasiDetectError::MissingExports`](crate::WasiDetectError::MissingExports).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingExportsError {
+    /// The version of the [`AutoWasi`](crate::AutoWasi).
+    pub version: WasiVersion,
+    /// The imports that can't be satisfied, in the order they appear in the module.
+    pub missing: Vec<MissingExport>,
+}
+
+impl fmt::Display for MissingExportsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "module imports {} items that aren't provided:",
+            self.version.module_name()
+        )?;
+        for (index, missing) in self.missing.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, missing)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingExportsError {}
+
+/// A problem found by [`WasiVersion::detect_resilient`](crate::WasiVersion::detect_resilient)
+/// that ended or prevented detection, without failing it.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum WasiDetectWarning {
+    /// An import couldn't be parsed. The imports after it in the section are skipped.
+    MalformedImport {
+        /// The position in the binary parsing failed at.
+        offset: usize,
+        /// The parse error.
+        error: BinaryReaderError,
+    },
+    /// A section couldn't be parsed before the imports were found, or the binary is truncated.
+    MalformedSection {
+        /// The position in the binary parsing failed at.
+        offset: usize,
+        /// The parse error.
+        error: BinaryReaderError,
+    },
+    /// The binary imports from more than one WASI version.
+    MixedVersions(MixedVersionsError),
+}
+
+impl fmt::Display for WasiDetectWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedImport { offset, error } => {
+                write!(
+                    f,
+                    "malformed import at offset {}: {}",
+                    offset,
+                    error.message()
+                )
+            }
+            Self::MalformedSection { offset, error } => {
+                write!(
+                    f,
+                    "malformed section at offset {}: {}",
+                    offset,
+                    error.message()
+                )
+            }
+            Self::MixedVersions(error) => error.fmt(f),
+        }
+    }
+}
+
+/// The error returned when a binary uses another WASI version than the one it was expected to.
+///
+/// Returned by [`AutoWasi::new_checked`](crate::AutoWasi::new_checked) as
+/// [`WasiDetectError::VersionMismatch`](crate::WasiDetectError::VersionMismatch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WasiVersionMismatchError {
+    /// The version the binary was expected to use.
+    pub expected: WasiVersion,
+    /// The version detected from the binary.
+    pub found: WasiVersion,
+}
+
+impl fmt::Display for WasiVersionMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected a binary importing {} but it imports {}",
+            self.expected.module_name(),
+            self.found.module_name()
+        )
+    }
+}
+
+impl std::error::Error for WasiVersionMismatchError {}
+
+impl From<WasiVersionMismatchError> for WasiDetectError {
+    fn from(error: WasiVersionMismatchError) -> Self {
+        Self::VersionMismatch(error)
+    }
+}
+
+/// The error returned when a binary uses a WASI version that doesn't satisfy a requirement.
+///
+/// Returned by
+/// [`WasiVersionRequirement::check_binary`](crate::WasiVersionRequirement::check_binary) as
+/// [`WasiDetectError::UnsatisfiedRequirement`](crate::WasiDetectError::UnsatisfiedRequirement).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequirementError {
+    /// The requirement the binary doesn't satisfy.
+    pub requirement: WasiVersionRequirement,
+    /// The version detected from the binary.
+    pub found: WasiVersion,
+}
+
+impl fmt::Display for RequirementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "binary imports {}, which doesn't satisfy the requirement {}",
+            self.found.module_name(),
+            self.requirement
+        )
+    }
+}
+
+impl std::error::Error for RequirementError {}
+
+impl From<RequirementError> for WasiDetectError {
+    fn from(error: RequirementError) -> Self {
+        Self::UnsatisfiedRequirement(error)
+    }
+}
+
+/// The error returned when parsing an unknown [`WasiVersion`](crate::WasiVersion) from a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWasiVersionError {
+    /// The string that was parsed.
+    pub input: String,
+}
+
+impl fmt::Display for ParseWasiVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown WASI version `{}`, expected snapshot0, snapshot1, preview2, wasix32v1 or wasix64v1",
+            self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseWasiVersionError {}
+
+/// The error returned when converting an integer that isn't the code of any
+/// [`WasiVersion`](crate::WasiVersion).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownWasiVersion(pub u32);
+
+impl fmt::Display for UnknownWasiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown WASI version code {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownWasiVersion {}
@@ -0,0 +1,75 @@
+//! Classifying binaries from the names they export, such as guessing the WASI version of binaries
+//! without WASI imports.
+
+use crate::WasiVersion;
+use wasmparser::{BinaryReaderError, Parser, Payload, SectionReader};
+
+/// The exports of a core module that WASI conventions give a meaning to.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Exports {
+    /// The `_start` function of command modules.
+    pub(crate) start: bool,
+    /// The `_initialize` function of reactor modules.
+    pub(crate) initialize: bool,
+    pub(crate) memory: bool,
+}
+
+impl Exports {
+    /// Collects the exports of the core module, without allocating.
+    ///
+    /// Returns `None` if the export section ends past `max_bytes`.
+    pub(crate) fn parse(
+        binary: &[u8],
+        max_bytes: usize,
+    ) -> Result<Option<Self>, BinaryReaderError> {
+        let mut exports = Self::default();
+
+        for payload in Parser::new(0).parse_all(binary) {
+            match payload? {
+                Payload::ExportSection(reader) => {
+                    if reader.range().end > max_bytes {
+                        return Ok(None);
+                    }
+                    for export in reader {
+                        match export?.field {
+                            "_start" => exports.start = true,
+                            "_initialize" => exports.initialize = true,
+                            "memory" => exports.memory = true,
+                            _ => {}
+                        }
+                    }
+                    break;
+                }
+                // The export section comes before the start, element, code and data sections.
+                Payload::StartSection { .. }
+                | Payload::ElementSection(_)
+                | Payload::DataCountSection { .. }
+                | Payload::CodeSectionStart { .. }
+                | Payload::DataSection(_)
+                | Payload::End => break,
+                _ => {}
+            }
+        }
+
+        Ok(Some(exports))
+    }
+}
+
+/// Guesses the version of a core module from the exports of the conventions it follows, without
+/// allocating.
+///
+/// Modules exporting `memory` and either `_start` or `_initialize` follow the preview1 command or
+/// reactor ABI and are guessed as [`WasiVersion::Snapshot1`]. Core modules aren't components, so
+/// they're never guessed as [`WasiVersion::Preview2`], even when they export the component
+/// model's `cabi_realloc`.
+///
+/// This is a best effort, binaries failing to parse or whose export section ends past
+/// `max_bytes` aren't guessed.
+pub(crate) fn guess(binary: &[u8], max_bytes: usize) -> Option<WasiVersion> {
+    let exports = Exports::parse(binary, max_bytes).ok()??;
+    if exports.memory && (exports.start || exports.initialize) {
+        Some(WasiVersion::Snapshot1)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+
+/// An experimental WASI proposal a binary imports from alongside a WASI version.
+///
+/// None of these are provided by an [`AutoWasi`](crate::AutoWasi), binaries importing them need a
+/// host that links them separately.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum WasiExtension {
+    /// wasi-nn, imported from `wasi_ephemeral_nn`.
+    #[cfg_attr(feature = "serde", serde(rename = "wasi-nn"))]
+    Nn,
+    /// wasi-crypto, imported from the `wasi_ephemeral_crypto_*` modules such as
+    /// `wasi_ephemeral_crypto_common`.
+    #[cfg_attr(feature = "serde", serde(rename = "wasi-crypto"))]
+    Crypto,
+    /// wasi-threads, the `thread-spawn` function imported from `wasi`.
+    #[cfg_attr(feature = "serde", serde(rename = "wasi-threads"))]
+    Threads,
+}
+
+impl WasiExtension {
+    /// Returns the extension the import belongs to, if any.
+    pub fn from_import(module: &str, name: &str) -> Option<Self> {
+        match module {
+            "wasi_ephemeral_nn" => Some(Self::Nn),
+            "wasi" if name == "thread-spawn" => Some(Self::Threads),
+            _ if module.starts_with("wasi_ephemeral_crypto") => Some(Self::Crypto),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the extension as the name of its proposal, such as `wasi-nn`.
+impl fmt::Display for WasiExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nn => write!(f, "wasi-nn"),
+            Self::Crypto => write!(f, "wasi-crypto"),
+            Self::Threads => write!(f, "wasi-threads"),
+        }
+    }
+}
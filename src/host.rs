@@ -0,0 +1,235 @@
+//! Building the `wasmtime-wasi` instances around a context the caller keeps.
+//!
+//! `wasmtime-wasi` moves the context into each instance it creates, the instances built here
+//! borrow it from a shared [`Rc`](std::rc::Rc) instead, so several instances can use one context.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+use wasi_common::WasiCtx;
+use wasmtime::{Caller, Extern, Func, Store, Trap};
+use wasmtime_wiggle::WasmtimeGuestMemory;
+
+/// Records a call to a function, with the time it started unless it doesn't return.
+pub(crate) type Recorder = Box<dyn Fn(Option<Instant>)>;
+
+fn memory(caller: &Caller<'_>) -> Result<WasmtimeGuestMemory, Trap> {
+    match caller.get_export("memory") {
+        Some(Extern::Memory(memory)) => Ok(WasmtimeGuestMemory::new(memory)),
+        _ => Err(Trap::new("missing required memory export")),
+    }
+}
+
+/// Emits an event with the module, function and arguments of a guest call if `traced` is set.
+#[cfg(feature = "tracing")]
+macro_rules! trace {
+    ($traced:ident, $module:ident, $name:ident($($arg:ident),*)) => {
+        if $traced {
+            tracing::trace!(
+                $module,
+                function = stringify!($name),
+                $($arg,)*
+                "guest called WASI function"
+            );
+        }
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace {
+    ($traced:ident, $module:ident, $name:ident($($arg:ident),*)) => {
+        let _ = ($traced, $module);
+    };
+}
+
+/// Returns a `proc_exit` which exits like the one of `wasmtime-wasi`.
+fn proc_exit(store: &Store, module: &'static str, traced: bool, record: Option<Recorder>) -> Func {
+    Func::wrap(store, move |status: i32| -> Result<(), Trap> {
+        trace!(traced, module, proc_exit(status));
+        if let Some(record) = &record {
+            record(None);
+        }
+        if (0..126).contains(&status) {
+            Err(Trap::i32_exit(status))
+        } else {
+            Err(Trap::new(
+                "exit with invalid exit status outside of [0..126)",
+            ))
+        }
+    })
+}
+
+/// Returns the function calling the `wasi-common` implementation like `wasmtime-wasi` does,
+/// tracing each call if `traced` is set and recording it if there's a recorder.
+macro_rules! wrap {
+    (
+        $store:ident,
+        $module:expr,
+        $traced:ident,
+        $ctx:ident,
+        $record:expr,
+        $target:path,
+        $name:ident($($arg:ident: $ty:ty),*)
+    ) => {{
+        let ctx = $ctx.clone();
+        let module: &'static str = $module;
+        let traced = $traced;
+        let record = $record;
+        Func::wrap(
+            $store,
+            move |caller: Caller<'_>, $($arg: $ty),*| -> Result<i32, Trap> {
+                trace!(traced, module, $name($($arg),*));
+                let memory = memory(&caller)?;
+                let start = Instant::now();
+                let result = {
+                    use $target as target;
+                    target::$name(&ctx.borrow(), &memory, $($arg),*)
+                };
+                if let Some(record) = &record {
+                    record(Some(start));
+                }
+                result.map_err(Trap::new)
+            },
+        )
+    }};
+}
+
+/// Implements the instances of both snapshots, which have the same functions.
+macro_rules! instances {
+    ($($name:ident($($arg:ident: $ty:ty),*);)*) => {
+        /// Creates a `wasi_unstable` instance using the context, tracing the guest's calls if
+        /// `traced` is set and with the recorders `recorder` returns for each function.
+        #[cfg(feature = "snapshot0")]
+        pub(crate) fn snapshot0(
+            store: &Store,
+            ctx: &Rc<RefCell<WasiCtx>>,
+            traced: bool,
+            mut recorder: impl FnMut(&'static str) -> Option<Recorder>,
+        ) -> wasmtime_wasi::old::snapshot_0::Wasi {
+            let module = "wasi_unstable";
+            wasmtime_wasi::old::snapshot_0::Wasi {
+                proc_exit: proc_exit(store, module, traced, recorder("proc_exit")),
+                $($name: wrap!(
+                    store,
+                    module,
+                    traced,
+                    ctx,
+                    recorder(stringify!($name)),
+                    wasi_common::snapshots::wasi_unstable::wasi_unstable,
+                    $name($($arg: $ty),*)
+                ),)*
+            }
+        }
+
+        /// Creates a `wasi_snapshot_preview1` instance using the context, tracing the guest's
+        /// calls if `traced` is set and with the recorders `recorder` returns for each function.
+        #[cfg(feature = "snapshot1")]
+        pub(crate) fn snapshot1(
+            store: &Store,
+            ctx: &Rc<RefCell<WasiCtx>>,
+            traced: bool,
+            mut recorder: impl FnMut(&'static str) -> Option<Recorder>,
+        ) -> wasmtime_wasi::Wasi {
+            let module = "wasi_snapshot_preview1";
+            wasmtime_wasi::Wasi {
+                proc_exit: proc_exit(store, module, traced, recorder("proc_exit")),
+                $($name: wrap!(
+                    store,
+                    module,
+                    traced,
+                    ctx,
+                    recorder(stringify!($name)),
+                    wasi_common::wasi::wasi_snapshot_preview1,
+                    $name($($arg: $ty),*)
+                ),)*
+            }
+        }
+    };
+}
+
+instances! {
+    args_get(argv: i32, argv_buf: i32);
+    args_sizes_get(argc: i32, argv_buf_size: i32);
+    clock_res_get(id: i32, resolution: i32);
+    clock_time_get(id: i32, precision: i64, time: i32);
+    environ_get(environ: i32, environ_buf: i32);
+    environ_sizes_get(environc: i32, environ_buf_size: i32);
+    fd_advise(fd: i32, offset: i64, len: i64, advice: i32);
+    fd_allocate(fd: i32, offset: i64, len: i64);
+    fd_close(fd: i32);
+    fd_datasync(fd: i32);
+    fd_fdstat_get(fd: i32, stat: i32);
+    fd_fdstat_set_flags(fd: i32, flags: i32);
+    fd_fdstat_set_rights(fd: i32, fs_rights_base: i64, fs_rights_inheriting: i64);
+    fd_filestat_get(fd: i32, buf: i32);
+    fd_filestat_set_size(fd: i32, size: i64);
+    fd_filestat_set_times(fd: i32, atim: i64, mtim: i64, fst_flags: i32);
+    fd_pread(fd: i32, iovs: i32, iovs_len: i32, offset: i64, nread: i32);
+    fd_prestat_dir_name(fd: i32, path: i32, path_len: i32);
+    fd_prestat_get(fd: i32, buf: i32);
+    fd_pwrite(fd: i32, iovs: i32, iovs_len: i32, offset: i64, nwritten: i32);
+    fd_read(fd: i32, iovs: i32, iovs_len: i32, nread: i32);
+    fd_readdir(fd: i32, buf: i32, buf_len: i32, cookie: i64, bufused: i32);
+    fd_renumber(fd: i32, to: i32);
+    fd_seek(fd: i32, offset: i64, whence: i32, newoffset: i32);
+    fd_sync(fd: i32);
+    fd_tell(fd: i32, offset: i32);
+    fd_write(fd: i32, iovs: i32, iovs_len: i32, nwritten: i32);
+    path_create_directory(fd: i32, path: i32, path_len: i32);
+    path_filestat_get(fd: i32, flags: i32, path: i32, path_len: i32, buf: i32);
+    path_filestat_set_times(
+        fd: i32,
+        flags: i32,
+        path: i32,
+        path_len: i32,
+        atim: i64,
+        mtim: i64,
+        fst_flags: i32
+    );
+    path_link(
+        old_fd: i32,
+        old_flags: i32,
+        old_path: i32,
+        old_path_len: i32,
+        new_fd: i32,
+        new_path: i32,
+        new_path_len: i32
+    );
+    path_open(
+        fd: i32,
+        dirflags: i32,
+        path: i32,
+        path_len: i32,
+        oflags: i32,
+        fs_rights_base: i64,
+        fs_rights_inheriting: i64,
+        fdflags: i32,
+        opened_fd: i32
+    );
+    path_readlink(fd: i32, path: i32, path_len: i32, buf: i32, buf_len: i32, bufused: i32);
+    path_remove_directory(fd: i32, path: i32, path_len: i32);
+    path_rename(
+        fd: i32,
+        old_path: i32,
+        old_path_len: i32,
+        new_fd: i32,
+        new_path: i32,
+        new_path_len: i32
+    );
+    path_symlink(old_path: i32, old_path_len: i32, fd: i32, new_path: i32, new_path_len: i32);
+    path_unlink_file(fd: i32, path: i32, path_len: i32);
+    poll_oneoff(subscriptions: i32, events: i32, nsubscriptions: i32, nevents: i32);
+    proc_raise(sig: i32);
+    random_get(buf: i32, buf_len: i32);
+    sched_yield();
+    sock_recv(
+        fd: i32,
+        ri_data: i32,
+        ri_data_len: i32,
+        ri_flags: i32,
+        ro_datalen: i32,
+        ro_flags: i32
+    );
+    sock_send(fd: i32, si_data: i32, si_data_len: i32, si_flags: i32, so_datalen: i32);
+    sock_shutdown(fd: i32, how: i32);
+}
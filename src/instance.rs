@@ -0,0 +1,100 @@
+//! Helpers for the instance structs `wasmtime-wasi` generates for each snapshot.
+
+/// Implements the helpers for both snapshots, which have the same functions.
+macro_rules! instances {
+    ($($name:ident,)*) => {
+        /// Clones the functions of the instance, which share the original's context.
+        #[cfg(feature = "snapshot0")]
+        pub(crate) fn clone_snapshot0(
+            wasi: &wasmtime_wasi::old::snapshot_0::Wasi,
+        ) -> wasmtime_wasi::old::snapshot_0::Wasi {
+            wasmtime_wasi::old::snapshot_0::Wasi {
+                $($name: wasi.$name.clone(),)*
+            }
+        }
+
+        /// Clones the functions of the instance, which share the original's context.
+        #[cfg(feature = "snapshot1")]
+        pub(crate) fn clone_snapshot1(wasi: &wasmtime_wasi::Wasi) -> wasmtime_wasi::Wasi {
+            wasmtime_wasi::Wasi {
+                $($name: wasi.$name.clone(),)*
+            }
+        }
+
+        /// Replaces the function of the instance with the name, returning whether it has one.
+        #[cfg(feature = "snapshot0")]
+        pub(crate) fn replace_snapshot0(
+            wasi: &mut wasmtime_wasi::old::snapshot_0::Wasi,
+            name: &str,
+            func: wasmtime::Func,
+        ) -> bool {
+            match name {
+                $(stringify!($name) => wasi.$name = func,)*
+                _ => return false,
+            }
+            true
+        }
+
+        /// Replaces the function of the instance with the name, returning whether it has one.
+        #[cfg(feature = "snapshot1")]
+        pub(crate) fn replace_snapshot1(
+            wasi: &mut wasmtime_wasi::Wasi,
+            name: &str,
+            func: wasmtime::Func,
+        ) -> bool {
+            match name {
+                $(stringify!($name) => wasi.$name = func,)*
+                _ => return false,
+            }
+            true
+        }
+    };
+}
+
+instances! {
+    args_get,
+    args_sizes_get,
+    clock_res_get,
+    clock_time_get,
+    environ_get,
+    environ_sizes_get,
+    fd_advise,
+    fd_allocate,
+    fd_close,
+    fd_datasync,
+    fd_fdstat_get,
+    fd_fdstat_set_flags,
+    fd_fdstat_set_rights,
+    fd_filestat_get,
+    fd_filestat_set_size,
+    fd_filestat_set_times,
+    fd_pread,
+    fd_prestat_dir_name,
+    fd_prestat_get,
+    fd_pwrite,
+    fd_read,
+    fd_readdir,
+    fd_renumber,
+    fd_seek,
+    fd_sync,
+    fd_tell,
+    fd_write,
+    path_create_directory,
+    path_filestat_get,
+    path_filestat_set_times,
+    path_link,
+    path_open,
+    path_readlink,
+    path_remove_directory,
+    path_rename,
+    path_symlink,
+    path_unlink_file,
+    poll_oneoff,
+    proc_exit,
+    proc_raise,
+    random_get,
+    sched_yield,
+    sock_recv,
+    sock_send,
+    sock_shutdown,
+}
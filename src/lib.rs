@@ -3,7 +3,7 @@
 //! used by the module.
 //!
 //! # Example
-//! 
+//!
 //! ```rust
 //! # use auto_wasi::*;
 //! # use wasmtime::*;
@@ -11,108 +11,2467 @@
 //! # fn test() -> anyhow::Result<()> {
 //! let wat = r#"
 //! (module
-//!     (type $empty (func))
-//!     ;; In the real world this would be an actual wasi import,
-//!     ;; but this crate only checks the module name.
-//!     (import "wasi_snapshot_preview1" "" (func (type $empty)))
-//!  )
+//!     (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+//! )
 //! "#;
 //! let store = Store::default();
 //! let ctx = WasiCtx::new(std::env::args())?;
 //!
 //! let wasm = wat::parse_str(wat)?;
-//! let wasi = AutoWasi::detect(&store, ctx, wasm)?;
+//! let (instance, version) = AutoWasi::instantiate(&store, ctx, &wasm)?;
 //! # Ok(()) }
 //! ```
-use anyhow::Result;
+//!
+//! To link other functions alongside WASI's, create the [`AutoWasi`] with
+//! [`AutoWasi::detect`] and add it to a [`Linker`](wasmtime::Linker) with
+//! [`AutoWasi::add_to_linker`].
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 use wasi_common::WasiCtx;
-use wasmparser::{Parser, Payload};
-use wasmtime::{Func, Linker, Store};
+use wasmparser::{
+    Chunk, Import, ImportSectionEntryType, ImportSectionReader, Parser, Payload, SectionReader,
+    TypeDef, TypeSectionReader,
+};
+use wasmtime::{
+    Engine, ExternType, Func, FuncType, Instance, Linker, Module, Store, Trap, ValType,
+};
+
+#[cfg(not(any(feature = "snapshot0", feature = "snapshot1")))]
+compile_error!("at least one of the `snapshot0` and `snapshot1` features must be enabled");
+
+mod abi;
+mod builder;
+#[cfg(feature = "cache")]
+mod cache;
+mod capabilities;
+mod capture;
+mod clock;
+mod command;
+mod component;
+mod config;
+mod detector;
+mod error;
+mod exports;
+mod extension;
+#[cfg(any(
+    feature = "metrics",
+    feature = "tracing",
+    all(feature = "snapshot0", feature = "snapshot1")
+))]
+mod host;
+mod instance;
+mod limits;
+mod linker;
+#[cfg(feature = "metrics")]
+mod metrics;
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+mod mixed;
+mod no_alloc;
+mod policy;
+mod report;
+mod requirement;
+#[cfg(feature = "rewrite")]
+pub mod rewrite;
+mod set;
+
+pub use builder::AutoWasiBuilder;
+#[cfg(feature = "cache")]
+pub use cache::WasiVersionCache;
+pub use capabilities::{Capability, RequiredCapabilities};
+pub use capture::{AutoWasiWithCapture, CapturedIo};
+pub use clock::DeterministicConfig;
+pub use command::{ExitStatus, ModuleKind, Reactor};
+pub use config::{DetectionConfig, UnknownNamespaceAction};
+pub use detector::WasiVersionDetector;
+pub use error::{
+    MissingExportsError, MixedVersionsError, ParseWasiVersionError, RequirementError,
+    UnknownWasiVersion, WasiDetectError, WasiDetectWarning, WasiVersionMismatchError,
+};
+pub use extension::WasiExtension;
+pub use limits::DetectLimits;
+pub use linker::Conflict;
+#[cfg(feature = "metrics")]
+pub use metrics::WasiMetrics;
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+pub use mixed::MixedWasi;
+pub use policy::{Denial, Policy};
+pub use report::{
+    DetectionReport, ExtensionImports, ImportKind, ImportReport, MissingExport, SignatureMismatch,
+    UnresolvedImport, UnresolvedImports, VersionSource, WasiImport,
+};
+pub use requirement::WasiVersionRequirement;
+pub use set::{WasiVersionSet, WasiVersionSetIter};
 
 /// An instantiated instance of the wasi exports.
 ///
 /// This represents a wasi module which can be used to instantiate other wasm modules.
 /// This structure exports all that various fields of the wasi instance as fields which can be used to implement your own instantiation logic, if necessary.
 /// Additionally [`AutoWasi::get_export`](crate::AutoWasi::get_export) can be used to do name-based resolution.
+///
+/// # Tracing guest calls
+///
+/// Every WASI call a guest makes, except `proc_exit`, already runs in a trace level
+/// `wiggle abi` span of the [`tracing`](https://docs.rs/tracing) crate, recording the `module`
+/// and `function` called. The span contains events with the decoded arguments and the returned
+/// errno. These come from the bindings `wasi-common` generates. Install a subscriber enabling the
+/// trace level for the `wasi_common` target to get the sequence of calls a guest made.
+///
+/// With the `tracing` feature, [`AutoWasi::new_traced`](crate::AutoWasi::new_traced) and
+/// [`AutoWasi::detect_traced`](crate::AutoWasi::detect_traced) also emit a trace level event for
+/// every call, `proc_exit` included, recording the `module`, `function` and the scalar arguments
+/// by name before calling `wasi-common`. Pointers are logged as integers.
 pub enum AutoWasi {
     /// WASI imports for the old `wasi_unstable` import module.
+    ///
+    /// `wasi-common` implements these by forwarding to its `wasi_snapshot_preview1`
+    /// implementation on the same [`WasiCtx`](wasi_common::WasiCtx), converting the old layouts
+    /// of types such as `whence`, `filestat` and `subscription` and the errno values on the way.
+    #[cfg(feature = "snapshot0")]
     Snapshot0(wasmtime_wasi::old::snapshot_0::Wasi),
     /// WASI imports for the current `wasi_snapshot_preview1` import module.
+    #[cfg(feature = "snapshot1")]
     Snapshot1(wasmtime_wasi::Wasi),
 }
 
 impl AutoWasi {
     /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the detected
     /// wasi version.
-    pub fn detect<T: AsRef<[u8]>>(store: &Store, ctx: WasiCtx, binary: T) -> Result<Self> {
+    ///
+    /// Imports from experimental WASI extensions aren't provided, use
+    /// [`WasiVersion::detect_extensions`](crate::WasiVersion::detect_extensions) to find binaries
+    /// that need them.
+    pub fn detect<T: AsRef<[u8]>>(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: T,
+    ) -> Result<Self, WasiDetectError> {
+        let version = WasiVersion::detect(binary)?;
+        Self::new(store, ctx, version)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the detected
+    /// wasi version, or `None` if the binary doesn't import any WASI version.
+    pub fn try_detect<T: AsRef<[u8]>>(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: T,
+    ) -> Result<Option<Self>, WasiDetectError> {
+        match WasiVersion::try_detect(binary)? {
+            Some(version) => Self::new(store, ctx, version).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the detected
+    /// wasi version, failing with
+    /// [`WasiDetectError::NoWasiImports`](crate::WasiDetectError::NoWasiImports) if the binary
+    /// doesn't import any WASI version.
+    pub fn detect_strict<T: AsRef<[u8]>>(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: T,
+    ) -> Result<Self, WasiDetectError> {
+        let version = WasiVersion::detect_strict(binary)?;
+        Self::new(store, ctx, version)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the wasi version
+    /// detected from the compiled module.
+    pub fn for_module(
+        store: &Store,
+        ctx: WasiCtx,
+        module: &Module,
+    ) -> Result<Self, WasiDetectError> {
+        let version = WasiVersion::detect_from_module(module)?;
+        Self::new(store, ctx, version)
+    }
+
+    /// Compiles the binary, links the WASI version detected from its imports and instantiates it,
+    /// returning the instance and the linked version.
+    ///
+    /// Fails with [`WasiDetectError::Compile`](crate::WasiDetectError::Compile),
+    /// [`WasiDetectError::Link`](crate::WasiDetectError::Link) or
+    /// [`WasiDetectError::Instantiate`](crate::WasiDetectError::Instantiate) depending on the
+    /// stage that failed, or with the detection error if the version can't be detected. Binaries
+    /// importing anything but WASI functions fail to instantiate, link those with a
+    /// [`Linker`](wasmtime::Linker) instead.
+    pub fn instantiate(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: &[u8],
+    ) -> Result<(Instance, WasiVersion), WasiDetectError> {
+        let module = Module::new(store.engine(), binary).map_err(WasiDetectError::Compile)?;
+        let wasi = Self::for_module(store, ctx, &module)?;
+        let mut linker = Linker::new(store);
+        wasi.add_to_linker(&mut linker)?;
+        let instance = linker
+            .instantiate(&module)
+            .map_err(WasiDetectError::Instantiate)?;
+        Ok((instance, wasi.version()))
+    }
+
+    /// Instantiates the command module like [`AutoWasi::instantiate`](crate::AutoWasi::instantiate)
+    /// and runs its `_start` function, returning the status it exits with.
+    ///
+    /// Returning from `_start` exits with code 0, calling `proc_exit` with the code passed to it.
+    /// Fails with [`WasiDetectError::MissingFunction`](crate::WasiDetectError::MissingFunction) if the
+    /// module doesn't export a `_start` function without params and results, and with
+    /// [`WasiDetectError::Trap`](crate::WasiDetectError::Trap) if it traps.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let wasm = wat::parse_str(
+    ///     r#"(module
+    ///         (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    ///         (func (export "_start") (call $proc_exit (i32.const 2)))
+    ///     )"#,
+    /// )?;
+    /// let store = Store::default();
+    /// let status = AutoWasi::run_command(&store, WasiCtx::new(std::env::args())?, &wasm)?;
+    /// assert_eq!(status.code(), Some(2));
+    /// # Ok(()) }
+    /// ```
+    pub fn run_command(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: &[u8],
+    ) -> Result<ExitStatus, WasiDetectError> {
+        let (instance, _) = Self::instantiate(store, ctx, binary)?;
+        let start = instance.get_func("_start").filter(|start| {
+            let ty = start.ty();
+            ty.params().len() == 0 && ty.results().len() == 0
+        });
+        let start = start.ok_or_else(|| command::missing_function(&instance, "_start"))?;
+
+        match start.call(&[]) {
+            Ok(_) => Ok(ExitStatus::new(0)),
+            Err(error) => {
+                let trap = command::trap(error);
+                match trap.i32_exit_status() {
+                    Some(code) => Ok(ExitStatus::new(code)),
+                    None => Err(WasiDetectError::Trap(trap)),
+                }
+            }
+        }
+    }
+
+    /// Instantiates the reactor module like
+    /// [`AutoWasi::instantiate`](crate::AutoWasi::instantiate) and calls its `_initialize`
+    /// function once, if it exports one.
+    ///
+    /// Fails with [`WasiDetectError::CommandModule`](crate::WasiDetectError::CommandModule) for
+    /// [command](crate::ModuleKind::Command) modules, and with
+    /// [`WasiDetectError::Trap`](crate::WasiDetectError::Trap) if `_initialize` traps.
+    pub fn instantiate_reactor(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: &[u8],
+    ) -> Result<Reactor, WasiDetectError> {
+        if ModuleKind::detect(binary)? == ModuleKind::Command {
+            return Err(WasiDetectError::CommandModule);
+        }
+
+        let (instance, version) = Self::instantiate(store, ctx, binary)?;
+        if let Some(initialize) = instance.get_func("_initialize") {
+            initialize
+                .call(&[])
+                .map_err(|error| WasiDetectError::Trap(command::trap(error)))?;
+        }
+        Ok(Reactor::new(instance, version))
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the wasi version
+    /// detected from the text format module.
+    #[cfg(feature = "wat")]
+    pub fn detect_wat(store: &Store, ctx: WasiCtx, wat: &str) -> Result<Self, WasiDetectError> {
+        let version = WasiVersion::detect_wat(wat)?;
+        Self::new(store, ctx, version)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version, with a context
+    /// inheriting the host's stdio.
+    ///
+    /// The binary is detected before the context is built, so invalid binaries fail with
+    /// [`WasiDetectError::Parse`](crate::WasiDetectError::Parse).
+    pub fn with_inherit_stdio<T: AsRef<[u8]>>(
+        store: &Store,
+        binary: T,
+    ) -> Result<Self, WasiDetectError> {
+        AutoWasiBuilder::new().inherit_stdio().detect(store, binary)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version, with a context
+    /// passing the environment variables to the module.
+    ///
+    /// Use an [`AutoWasiBuilder`](crate::AutoWasiBuilder) to combine envs with other options,
+    /// such as inherited stdio.
+    pub fn with_envs<K, V, T>(
+        store: &Store,
+        envs: impl IntoIterator<Item = (K, V)>,
+        binary: T,
+    ) -> Result<Self, WasiDetectError>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+        T: AsRef<[u8]>,
+    {
+        let envs: Vec<_> = envs.into_iter().collect();
+        AutoWasiBuilder::new()
+            .envs(envs.iter().map(|(k, v)| (k.as_ref(), v.as_ref())))
+            .detect(store, binary)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version, with a context
+    /// passing the args and environment variables to the module.
+    ///
+    /// The first arg is the program name by convention, such as `app` in `["app", "--verbose"]`.
+    pub fn with_args_and_envs<T: AsRef<[u8]>>(
+        store: &Store,
+        args: &[&str],
+        envs: &[(&str, &str)],
+        binary: T,
+    ) -> Result<Self, WasiDetectError> {
+        AutoWasiBuilder::new()
+            .args(args.iter().copied())
+            .envs(envs.iter().copied())
+            .detect(store, binary)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version, with a context
+    /// writing stdout and stderr to in-memory buffers.
+    ///
+    /// Take the output from the buffers with
+    /// [`AutoWasiWithCapture::take_stdout`](crate::AutoWasiWithCapture::take_stdout) and
+    /// [`AutoWasiWithCapture::take_stderr`](crate::AutoWasiWithCapture::take_stderr). Stdin is
+    /// empty.
+    pub fn with_stdio_capture<T: AsRef<[u8]>>(
+        store: &Store,
+        binary: T,
+    ) -> Result<AutoWasiWithCapture, WasiDetectError> {
+        let (wasi, io) = Self::detect_captured(store, binary)?;
+        Ok(AutoWasiWithCapture::new(wasi, io))
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version, with a context
+    /// writing stdout and stderr to the returned [`CapturedIo`](crate::CapturedIo).
+    ///
+    /// The buffers aren't limited, use [`CapturedIo::with_limit`](crate::CapturedIo::with_limit)
+    /// and [`CapturedIo::ctx`](crate::CapturedIo::ctx) to limit them.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # fn test(wasm: &[u8]) -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let (wasi, captured) = AutoWasi::detect_captured(&store, wasm)?;
+    /// let instance = wasi.into_linker(&store)?.instantiate(&Module::new(store.engine(), wasm)?)?;
+    /// instance.get_func("_start").unwrap().call(&[])?;
+    /// assert_eq!(captured.stdout_lossy(), "hello\n");
+    /// # Ok(()) }
+    /// ```
+    pub fn detect_captured<T: AsRef<[u8]>>(
+        store: &Store,
+        binary: T,
+    ) -> Result<(Self, CapturedIo), WasiDetectError> {
+        let version = WasiVersion::detect(binary)?;
+        let io = CapturedIo::new();
+        let wasi = Self::new(store, io.ctx()?, version)?;
+        Ok((wasi, io))
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version whose
+    /// `clock_time_get` function reads the time from `clock_fn`, in nanoseconds.
+    ///
+    /// `wasi-common` has no API to replace the clocks of a context, so the instance's
+    /// `clock_time_get` function is replaced instead. It answers every clock with `clock_fn`,
+    /// including `realtime`, which makes the time deterministic for tests and replays. Calls
+    /// with an unknown clock fail with the `inval` errno, and the function traps if the module
+    /// doesn't export its memory.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test(wasm: &[u8]) -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let ctx = WasiCtx::new(std::env::args())?;
+    /// // Every module run sees 2021-01-01T00:00:00Z.
+    /// let wasi = AutoWasi::with_clock_override(&store, ctx, wasm, || 1_609_459_200_000_000_000)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn with_clock_override<T: AsRef<[u8]>>(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: T,
+        clock_fn: impl Fn() -> u64 + 'static,
+    ) -> Result<Self, WasiDetectError> {
+        let mut wasi = Self::detect(store, ctx, binary)?;
+        wasi.replace(
+            "clock_time_get",
+            clock::clock_time_get(store, move |_| clock_fn()),
+        )?;
+        Ok(wasi)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the provided version whose clocks and
+    /// entropy are virtual, so runs of a module with the same context give the same results.
+    ///
+    /// Each call to `clock_time_get` advances the clocks by the config's
+    /// [`tick`](crate::DeterministicConfig::tick). The `realtime` clock starts at its
+    /// [`start_time`](crate::DeterministicConfig::start_time), the other clocks at 0. The bytes
+    /// `random_get` returns come from a generator seeded with its
+    /// [`seed`](crate::DeterministicConfig::seed). Everything else, such as args, envs and stdio,
+    /// comes from the context as usual.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let ctx = WasiCtx::new(std::env::args())?;
+    /// let config = DeterministicConfig {
+    ///     seed: 42,
+    ///     ..DeterministicConfig::default()
+    /// };
+    /// let wasi = AutoWasi::deterministic(&store, ctx, WasiVersion::Snapshot1, config)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn deterministic(
+        store: &Store,
+        ctx: WasiCtx,
+        version: WasiVersion,
+        config: DeterministicConfig,
+    ) -> Result<Self, WasiDetectError> {
+        Self::with_overrides(store, ctx, version, config.functions(store))
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the provided version whose functions
+    /// named in `overrides` are replaced, such as a deterministic `random_get` for tests.
+    ///
+    /// The overrides take the place of the `wasmtime-wasi` functions, so
+    /// [`get_export`](AutoWasi::get_export) returns them and every method adding this instance
+    /// to a [`Linker`](wasmtime::Linker) links them. Functions that aren't overridden keep
+    /// their implementation. Fails with
+    /// [`WasiDetectError::UnknownOverride`](crate::WasiDetectError::UnknownOverride) for names
+    /// that aren't functions of the version, and with
+    /// [`WasiDetectError::OverrideMismatch`](crate::WasiDetectError::OverrideMismatch) for
+    /// overrides whose type isn't the one WASI defines for the function.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let sched_yield = Func::wrap(&store, || 0);
+    /// let wasi = AutoWasi::with_overrides(
+    ///     &store,
+    ///     WasiCtx::new(std::env::args())?,
+    ///     WasiVersion::Snapshot1,
+    ///     vec![("sched_yield", sched_yield)],
+    /// )?;
+    /// # Ok(()) }
+    /// ```
+    pub fn with_overrides<I, S>(
+        store: &Store,
+        ctx: WasiCtx,
+        version: WasiVersion,
+        overrides: I,
+    ) -> Result<Self, WasiDetectError>
+    where
+        I: IntoIterator<Item = (S, Func)>,
+        S: AsRef<str>,
+    {
+        let mut wasi = Self::new(store, ctx, version)?;
+        for (name, func) in overrides {
+            wasi.replace(name.as_ref(), func)?;
+        }
+        Ok(wasi)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the provided version whose functions count
+    /// their calls and the time spent in them, returning it with the
+    /// [`WasiMetrics`](crate::WasiMetrics) they record to.
+    ///
+    /// The functions call the same `wasi-common` implementations as the ones of
+    /// `wasmtime-wasi`, with a relaxed atomic add and a clock read around each call. Fails like
+    /// [`AutoWasi::new`](crate::AutoWasi::new) for versions without an implementation.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test(module: &Module) -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let ctx = WasiCtx::new(std::env::args())?;
+    /// let (wasi, metrics) = AutoWasi::with_metrics(&store, ctx, WasiVersion::Snapshot1)?;
+    /// let instance = wasi.into_linker(&store)?.instantiate(module)?;
+    /// instance.get_func("_start").unwrap().call(&[])?;
+    /// for (name, calls, duration) in metrics.iter() {
+    ///     println!("{}: {} calls in {:?}", name, calls, duration);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(
+        store: &Store,
+        ctx: WasiCtx,
+        version: WasiVersion,
+    ) -> Result<(Self, WasiMetrics), WasiDetectError> {
+        match version {
+            #[cfg(feature = "snapshot0")]
+            WasiVersion::Snapshot0 => {
+                let (wasi, metrics) = metrics::snapshot0(store, ctx);
+                Ok((Self::Snapshot0(wasi), metrics))
+            }
+            #[cfg(feature = "snapshot1")]
+            WasiVersion::Snapshot1 => {
+                let (wasi, metrics) = metrics::snapshot1(store, ctx);
+                Ok((Self::Snapshot1(wasi), metrics))
+            }
+            version => Err(WasiDetectError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the provided version emitting a trace
+    /// level event for every call the guest makes.
+    ///
+    /// See [Tracing guest calls](crate::AutoWasi#tracing-guest-calls) for what the events record.
+    #[cfg(feature = "tracing")]
+    pub fn new_traced(
+        store: &Store,
+        ctx: WasiCtx,
+        version: WasiVersion,
+    ) -> Result<Self, WasiDetectError> {
+        let ctx = std::rc::Rc::new(std::cell::RefCell::new(ctx));
+        match version {
+            #[cfg(feature = "snapshot0")]
+            WasiVersion::Snapshot0 => {
+                Ok(Self::Snapshot0(host::snapshot0(store, &ctx, true, |_| {
+                    None
+                })))
+            }
+            #[cfg(feature = "snapshot1")]
+            WasiVersion::Snapshot1 => {
+                Ok(Self::Snapshot1(host::snapshot1(store, &ctx, true, |_| {
+                    None
+                })))
+            }
+            version => Err(WasiDetectError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the wasi version detected from the binary,
+    /// emitting a trace level event for every call the guest makes.
+    #[cfg(feature = "tracing")]
+    pub fn detect_traced<T: AsRef<[u8]>>(
+        store: &Store,
+        ctx: WasiCtx,
+        binary: T,
+    ) -> Result<Self, WasiDetectError> {
         let version = WasiVersion::detect(binary)?;
-        Ok(Self::new(store, ctx, version))
+        Self::new_traced(store, ctx, version)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the provided version whose functions the
+    /// policy denies fail instead of reaching `wasi-common`.
+    ///
+    /// Denied functions return the errno of the policy's [`Denial`](crate::Denial) or trap,
+    /// a denied `proc_exit` always traps. The context is still used by the allowed functions,
+    /// so it should only hold what those need.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let mut policy = Policy::new();
+    /// policy.deny(Capability::Filesystem).deny(Capability::Sockets);
+    /// let store = Store::default();
+    /// let ctx = WasiCtx::new(std::env::args())?;
+    /// let wasi = AutoWasi::with_policy(&store, ctx, WasiVersion::Snapshot1, &policy)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn with_policy(
+        store: &Store,
+        ctx: WasiCtx,
+        version: WasiVersion,
+        policy: &Policy,
+    ) -> Result<Self, WasiDetectError> {
+        Self::with_overrides(
+            store,
+            ctx,
+            version,
+            policy.denied_functions(store, &version),
+        )
+    }
+
+    /// Replaces the function with the name, checking the replacement has its type.
+    fn replace(&mut self, name: &str, func: Func) -> Result<(), WasiDetectError> {
+        let unknown = || WasiDetectError::UnknownOverride {
+            name: name.to_string(),
+        };
+        let expected = abi::function(&self.version(), name)
+            .ok_or_else(unknown)?
+            .ty();
+        let found = func.ty();
+        if found != expected {
+            return Err(WasiDetectError::OverrideMismatch {
+                name: name.to_string(),
+                expected,
+                found,
+            });
+        }
+
+        let replaced = match self {
+            #[cfg(feature = "snapshot0")]
+            Self::Snapshot0(wasi) => instance::replace_snapshot0(wasi, name, func),
+            #[cfg(feature = "snapshot1")]
+            Self::Snapshot1(wasi) => instance::replace_snapshot1(wasi, name, func),
+        };
+        if replaced {
+            Ok(())
+        } else {
+            Err(unknown())
+        }
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the detected wasi version, with a context
+    /// only granting the resources of the policy the binary's imports need.
+    ///
+    /// See [`AutoWasiBuilder::restrict`](crate::AutoWasiBuilder::restrict) for which resources are
+    /// kept.
+    pub fn detect_minimal<T: AsRef<[u8]>>(
+        store: &Store,
+        binary: T,
+        policy: &AutoWasiBuilder,
+    ) -> Result<Self, WasiDetectError> {
+        let imports = WasiImports::parse(binary.as_ref())?;
+        let version = imports.configured_version(&DetectionConfig::default())?;
+        policy
+            .restrict(&imports.capabilities())
+            .build(store, version)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the wasi version
+    /// detected from the binary at the path.
+    pub fn detect_from_path<P: AsRef<Path>>(
+        store: &Store,
+        ctx: WasiCtx,
+        path: P,
+    ) -> Result<Self, WasiDetectError> {
+        let version = WasiVersion::detect_from_path(path)?;
+        Self::new(store, ctx, version)
+    }
+
+    /// Lists the binary's imports from modules that aren't a WASI version, grouped by module in the
+    /// order they first appear.
+    ///
+    /// These have to be linked by the host alongside the [`AutoWasi`](crate::AutoWasi), which can
+    /// be checked before touching a [`Linker`](wasmtime::Linker).
+    pub fn unresolved_imports<T: AsRef<[u8]>>(
+        binary: T,
+    ) -> Result<Vec<UnresolvedImports>, WasiDetectError> {
+        Ok(WasiImports::parse(binary.as_ref())?.unresolved)
     }
 
     /// Creates a new [`AutoWasi`](crate::AutoWasi) that allows for linking from the provided
     /// [`WasiVersion`](crate::WasiVersion).
-    pub fn new(store: &Store, ctx: WasiCtx, version: WasiVersion) -> Self {
+    ///
+    /// Fails for [`WasiVersion::Preview2`](crate::WasiVersion::Preview2) as `wasmtime-wasi` has no
+    /// implementation of the component-model interfaces to link against, for the WASIX versions it
+    /// doesn't implement either, and for versions whose `snapshot0` or `snapshot1` feature is
+    /// disabled.
+    pub fn new(store: &Store, ctx: WasiCtx, version: WasiVersion) -> Result<Self, WasiDetectError> {
         match version {
+            #[cfg(feature = "snapshot0")]
             WasiVersion::Snapshot0 => {
-                let wasi = wasmtime_wasi::old::snapshot_0::Wasi::new(&store, ctx);
-                Self::Snapshot0(wasi)
+                let wasi = wasmtime_wasi::old::snapshot_0::Wasi::new(store, ctx);
+                Ok(Self::Snapshot0(wasi))
             }
+            #[cfg(feature = "snapshot1")]
             WasiVersion::Snapshot1 => {
-                let wasi = wasmtime_wasi::Wasi::new(&store, ctx);
-                Self::Snapshot1(wasi)
+                let wasi = wasmtime_wasi::Wasi::new(store, ctx);
+                Ok(Self::Snapshot1(wasi))
+            }
+            version => Err(WasiDetectError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Creates the imports of both snapshots backed by the same context, for modules importing
+    /// from `wasi_unstable` and `wasi_snapshot_preview1` at once.
+    ///
+    /// See [`MixedWasi`](crate::MixedWasi) for what the snapshots share.
+    #[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+    pub fn new_all(store: &Store, ctx: WasiCtx) -> MixedWasi {
+        MixedWasi::new(store, ctx)
+    }
+
+    /// Creates a new [`AutoWasi`](crate::AutoWasi) for the provided version, checking that the
+    /// binary uses it.
+    ///
+    /// Fails with [`WasiDetectError::VersionMismatch`](crate::WasiDetectError::VersionMismatch)
+    /// if another version is detected from the binary. Binaries without WASI imports match any
+    /// version.
+    pub fn new_checked<T: AsRef<[u8]>>(
+        store: &Store,
+        ctx: WasiCtx,
+        version: WasiVersion,
+        binary: T,
+    ) -> Result<Self, WasiDetectError> {
+        match WasiVersion::try_detect(binary)? {
+            Some(found) if found != version => Err(WasiVersionMismatchError {
+                expected: version,
+                found,
             }
+            .into()),
+            _ => Self::new(store, ctx, version),
+        }
+    }
+
+    /// Returns the [`WasiVersion`](crate::WasiVersion) this instance provides imports for.
+    pub fn version(&self) -> WasiVersion {
+        match self {
+            #[cfg(feature = "snapshot0")]
+            Self::Snapshot0(_) => WasiVersion::Snapshot0,
+            #[cfg(feature = "snapshot1")]
+            Self::Snapshot1(_) => WasiVersion::Snapshot1,
+        }
+    }
+
+    /// Returns the `wasi_unstable` instance, or `None` if this instance is for another version.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasi = AutoWasi::new(&store, WasiCtx::new(std::env::args())?, WasiVersion::Snapshot0)?;
+    /// let snapshot0: &old::snapshot_0::Wasi = wasi.as_snapshot0().unwrap();
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "snapshot0")]
+    pub fn as_snapshot0(&self) -> Option<&wasmtime_wasi::old::snapshot_0::Wasi> {
+        match self {
+            Self::Snapshot0(wasi) => Some(wasi),
+            #[cfg(feature = "snapshot1")]
+            Self::Snapshot1(_) => None,
+        }
+    }
+
+    /// Returns the `wasi_snapshot_preview1` instance, or `None` if this instance is for another
+    /// version.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasi = AutoWasi::new(&store, WasiCtx::new(std::env::args())?, WasiVersion::Snapshot1)?;
+    /// let snapshot1: &Wasi = wasi.as_snapshot1().unwrap();
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "snapshot1")]
+    pub fn as_snapshot1(&self) -> Option<&wasmtime_wasi::Wasi> {
+        match self {
+            #[cfg(feature = "snapshot0")]
+            Self::Snapshot0(_) => None,
+            Self::Snapshot1(wasi) => Some(wasi),
+        }
+    }
+
+    /// Converts into the `wasi_unstable` instance, or `None` if this instance is for another
+    /// version.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasi = AutoWasi::new(&store, WasiCtx::new(std::env::args())?, WasiVersion::Snapshot0)?;
+    /// let snapshot0: old::snapshot_0::Wasi = wasi.into_snapshot0().unwrap();
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "snapshot0")]
+    pub fn into_snapshot0(self) -> Option<wasmtime_wasi::old::snapshot_0::Wasi> {
+        match self {
+            Self::Snapshot0(wasi) => Some(wasi),
+            #[cfg(feature = "snapshot1")]
+            Self::Snapshot1(_) => None,
+        }
+    }
+
+    /// Converts into the `wasi_snapshot_preview1` instance, or `None` if this instance is for
+    /// another version.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasi = AutoWasi::new(&store, WasiCtx::new(std::env::args())?, WasiVersion::Snapshot1)?;
+    /// let snapshot1: Wasi = wasi.into_snapshot1().unwrap();
+    /// # Ok(()) }
+    /// ```
+    #[cfg(feature = "snapshot1")]
+    pub fn into_snapshot1(self) -> Option<wasmtime_wasi::Wasi> {
+        match self {
+            #[cfg(feature = "snapshot0")]
+            Self::Snapshot0(_) => None,
+            Self::Snapshot1(wasi) => Some(wasi),
+        }
+    }
+
+    /// Creates another instance with the same functions, sharing this instance's
+    /// [`WasiCtx`](wasi_common::WasiCtx).
+    ///
+    /// Args, environment, open files and anything else guests change in the context, such as
+    /// their working directory, are shared by both instances. There's no deep `Clone` as a
+    /// `WasiCtx` can't be cloned, its file handles are owned by the context. To get an instance
+    /// with a separate context create a new one from the same configuration, for example by
+    /// reusing an [`AutoWasiBuilder`](crate::AutoWasiBuilder).
+    pub fn shallow_clone(&self) -> Self {
+        match self {
+            #[cfg(feature = "snapshot0")]
+            Self::Snapshot0(wasi) => Self::Snapshot0(instance::clone_snapshot0(wasi)),
+            #[cfg(feature = "snapshot1")]
+            Self::Snapshot1(wasi) => Self::Snapshot1(instance::clone_snapshot1(wasi)),
         }
     }
 
     /// Looks up a field called name in this structure, returning it if found.
     /// This is often useful when instantiating a wasmtime instance where name resolution often happens with strings.
+    ///
+    /// wasmtime 0.22 has no `TypedFunc`, the typed accessors of [`Func`](wasmtime::Func) such as
+    /// [`get1`](wasmtime::Func::get1) check the signature instead, returning an error rather than
+    /// panicking on a mismatch:
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # fn test(wasi: &AutoWasi) -> anyhow::Result<()> {
+    /// let proc_exit = wasi.get_export("proc_exit").unwrap();
+    /// assert!(proc_exit.get1::<i32, ()>().is_ok());
+    /// assert!(proc_exit.get1::<i64, ()>().is_err());
+    /// # Ok(()) }
+    /// ```
     pub fn get_export(&self, name: &str) -> Option<&Func> {
         match self {
+            #[cfg(feature = "snapshot0")]
             Self::Snapshot0(wasi) => wasi.get_export(name),
+            #[cfg(feature = "snapshot1")]
             Self::Snapshot1(wasi) => wasi.get_export(name),
         }
     }
 
+    /// Lists the module's imports from this instance's WASI version that it doesn't provide, or
+    /// provides with a different type, in the order they appear.
+    ///
+    /// These imports fail to resolve when the module is instantiated, such as functions added to
+    /// the version after the bundled `wasmtime-wasi` was released.
+    pub fn missing_exports(&self, module: &Module) -> Vec<MissingExport> {
+        let namespace = self.version().module_name();
+        module
+            .imports()
+            .filter(|import| import.module() == namespace)
+            .filter_map(|import| {
+                let name = import.name().unwrap_or_default();
+                let ty = match import.ty() {
+                    ExternType::Func(ty) => Some(ty),
+                    _ => None,
+                };
+                let provided = self.get_export(name).map(Func::ty);
+                if provided.is_some() && provided == ty {
+                    return None;
+                }
+
+                Some(MissingExport {
+                    name: name.to_string(),
+                    kind: import_kind(&import.ty()),
+                    ty,
+                    provided,
+                })
+            })
+            .collect()
+    }
+
+    /// Sorts the binary's imports from this instance's WASI version by whether the instance
+    /// provides them with the type they're imported with, without compiling the binary.
+    ///
+    /// Imports from other modules aren't listed, see
+    /// [`unresolved_imports`](Self::unresolved_imports) for the ones the host has to link.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # fn test(wasi: &AutoWasi, wasm: &[u8]) -> anyhow::Result<()> {
+    /// let report = wasi.inspect_imports(wasm)?;
+    /// for name in &report.unsatisfied {
+    ///     eprintln!("{} isn't provided by {}", name, wasi.version());
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn inspect_imports<T: AsRef<[u8]>>(
+        &self,
+        binary: T,
+    ) -> Result<ImportReport, WasiDetectError> {
+        let WasiImports { types, imports, .. } = WasiImports::parse(binary.as_ref())?;
+        let namespace = self.version().module_name();
+        let mut report = ImportReport::default();
+        for (_, import) in imports {
+            if import.module != namespace {
+                continue;
+            }
+            let ty = import
+                .type_index
+                .and_then(|index| types.get(index as usize)?.as_ref());
+            let provided = self.get_export(&import.name).map(Func::ty);
+            if ty.is_some() && provided.as_ref() == ty {
+                report.satisfied.push(import.name);
+            } else {
+                report.unsatisfied.push(import.name);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Adds all instance items to the specified Linker after checking that they satisfy all of
+    /// the module's imports from this instance's WASI version.
+    ///
+    /// Fails with [`WasiDetectError::MissingExports`](crate::WasiDetectError::MissingExports)
+    /// listing every [missing export](Self::missing_exports) at once, instead of only the first
+    /// one when the module is instantiated.
+    pub fn add_to_linker_for_module(
+        &self,
+        linker: &mut Linker,
+        module: &Module,
+    ) -> Result<(), WasiDetectError> {
+        let missing = self.missing_exports(module);
+        if !missing.is_empty() {
+            return Err(WasiDetectError::MissingExports(MissingExportsError {
+                version: self.version(),
+                missing,
+            }));
+        }
+
+        self.add_to_linker(linker)
+    }
+
+    /// Adds all instance items to the specified Linker, then defines the module's remaining
+    /// function imports as functions that trap with the import's name when called.
+    ///
+    /// Imports the linker can already satisfy are left alone. Fails with
+    /// [`WasiDetectError::Unstubbable`](crate::WasiDetectError::Unstubbable) for any other import
+    /// that isn't a function, such as a memory or global, as those can't be stubbed.
+    pub fn add_to_linker_with_stubs(
+        &self,
+        linker: &mut Linker,
+        module: &Module,
+    ) -> Result<(), WasiDetectError> {
+        self.add_to_linker(linker)?;
+
+        for import in module.imports() {
+            if linker.get(&import).is_some() {
+                continue;
+            }
+
+            let name = import.name().unwrap_or_default();
+            let ty = match import.ty() {
+                ExternType::Func(ty) => ty,
+                ty => {
+                    return Err(WasiDetectError::Unstubbable {
+                        module: import.module().to_string(),
+                        name: name.to_string(),
+                        kind: import_kind(&ty),
+                    })
+                }
+            };
+            let message = format!("called unimplemented import `{}.{}`", import.module(), name);
+            let func = Func::new(linker.store(), ty, move |_, _, _| {
+                Err(Trap::new(message.clone()))
+            });
+            linker
+                .define(import.module(), name, func)
+                .map_err(WasiDetectError::Link)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds only the functions of this instance named in the allowlist to the specified Linker.
+    ///
+    /// Modules importing any other function of this instance's WASI version fail to
+    /// instantiate, unless the host defines it. Fails with
+    /// [`WasiDetectError::InvalidAllowlist`](crate::WasiDetectError::InvalidAllowlist) if the
+    /// allowlist is empty or names functions this instance doesn't have, without adding any.
+    pub fn add_to_linker_filtered(
+        &self,
+        linker: &mut Linker,
+        allow: &[&str],
+    ) -> Result<(), WasiDetectError> {
+        let unknown: Vec<_> = allow
+            .iter()
+            .filter(|name| self.get_export(name).is_none())
+            .map(|name| name.to_string())
+            .collect();
+        if allow.is_empty() || !unknown.is_empty() {
+            return Err(WasiDetectError::InvalidAllowlist { unknown });
+        }
+
+        let module = self.version().module_name();
+        for name in allow {
+            if let Some(func) = self.get_export(name) {
+                linker
+                    .define(module, name, func.clone())
+                    .map_err(WasiDetectError::Link)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds all instance items to the specified Linker under the provided module name, instead of
+    /// the module name of this instance's WASI version.
+    ///
+    /// This links binaries whose toolchain renamed the WASI module they import from, such as to
+    /// `wasi`. Fails with
+    /// [`WasiDetectError::EmptyModuleName`](crate::WasiDetectError::EmptyModuleName) if the
+    /// module name is empty, without adding any.
+    pub fn add_to_linker_named(
+        &self,
+        linker: &mut Linker,
+        module_name: &str,
+    ) -> Result<(), WasiDetectError> {
+        if module_name.is_empty() {
+            return Err(WasiDetectError::EmptyModuleName);
+        }
+
+        for (name, func) in self.exports() {
+            linker
+                .define(module_name, name, func.clone())
+                .map_err(WasiDetectError::Link)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds all instance items to the specified Linker, resolving the functions it already
+    /// defines according to `on_conflict`.
+    ///
+    /// With [`Conflict::KeepExisting`](crate::Conflict::KeepExisting) host overrides win and WASI
+    /// fills the gaps. The linker's shadowing setting is left as it was.
+    ///
+    /// The linker tells definitions apart by name and type, so a function defined with another
+    /// type than WASI's can't be kept or overwritten. Fails with
+    /// [`WasiDetectError::LinkerMismatch`](crate::WasiDetectError::LinkerMismatch) for those,
+    /// whatever `on_conflict` is, without adding any function.
+    pub fn add_to_linker_checked(
+        &self,
+        linker: &mut Linker,
+        on_conflict: Conflict,
+    ) -> Result<(), WasiDetectError> {
+        let module = self.version().module_name();
+        let mut conflicts = Vec::new();
+        let mut free = Vec::new();
+        for (name, func) in self.exports() {
+            let expected = func.ty();
+            let found: Vec<_> = linker
+                .get_by_name(module, Some(name))
+                .filter_map(|item| item.clone().into_func())
+                .map(|func| func.ty())
+                .collect();
+            if found.is_empty() {
+                free.push((name, func));
+            } else if found.contains(&expected) {
+                conflicts.push((name, func));
+            } else {
+                return Err(WasiDetectError::LinkerMismatch {
+                    module: module.to_string(),
+                    name: name.to_string(),
+                    expected,
+                    found: found[0].clone(),
+                });
+            }
+        }
+
+        match on_conflict {
+            Conflict::Error if !conflicts.is_empty() => {
+                return Err(WasiDetectError::Conflicts {
+                    module: module.to_string(),
+                    names: conflicts.iter().map(|(name, _)| name.to_string()).collect(),
+                });
+            }
+            Conflict::Overwrite if !conflicts.is_empty() => {
+                // `Linker` doesn't expose whether it allows shadowing, so the first redefinition
+                // tells: it only succeeds if shadowing was already allowed.
+                let (name, func) = conflicts[0];
+                if linker.define(module, name, func.clone()).is_err() {
+                    linker.allow_shadowing(true);
+                    let result = Self::define_all(linker, module, &conflicts);
+                    linker.allow_shadowing(false);
+                    result?;
+                } else {
+                    Self::define_all(linker, module, &conflicts[1..])?;
+                }
+            }
+            _ => {}
+        }
+
+        Self::define_all(linker, module, &free)
+    }
+
+    fn define_all(
+        linker: &mut Linker,
+        module: &str,
+        funcs: &[(&str, &Func)],
+    ) -> Result<(), WasiDetectError> {
+        for (name, func) in funcs {
+            linker
+                .define(module, name, (*func).clone())
+                .map_err(WasiDetectError::Link)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the name and function of every export of this instance, sorted by name.
+    ///
+    /// Yields the same functions as [`AutoWasi::get_export`](crate::AutoWasi::get_export) does
+    /// for each name.
+    pub fn exports(&self) -> impl Iterator<Item = (&str, &Func)> + '_ {
+        abi::functions(&self.version())
+            .iter()
+            .filter_map(move |function| Some((function.name, self.get_export(function.name)?)))
+    }
+
+    /// Returns the number of functions [`AutoWasi::add_to_linker`](crate::AutoWasi::add_to_linker)
+    /// adds, the same number [`AutoWasi::exports`](crate::AutoWasi::exports) yields.
+    pub fn get_function_count(&self) -> usize {
+        self.exports().count()
+    }
+
     /// Adds all instance items to the specified Linker.
-    pub fn add_to_linker(&self, linker: &mut Linker) -> Result<()> {
-        match self {
+    ///
+    /// This targets the non-generic [`Linker`](wasmtime::Linker) of wasmtime 0.22. The generic
+    /// `Linker<T>` only exists in later wasmtime releases, where stores own their host state and
+    /// `wasmtime-wasi` is linked with its own `add_to_linker` functions instead of instances.
+    ///
+    /// Fails with [`WasiDetectError::Link`](crate::WasiDetectError::Link) if the linker rejects
+    /// a definition, such as when it already defines one of the functions.
+    ///
+    /// With the `tracing` feature, each function is logged at the trace level before adding them.
+    pub fn add_to_linker(&self, linker: &mut Linker) -> Result<(), WasiDetectError> {
+        #[cfg(feature = "tracing")]
+        {
+            let module = self.version().module_name();
+            for (name, _) in self.exports() {
+                tracing::trace!(module, function = name, "adding WASI function to linker");
+            }
+        }
+
+        let result = match self {
+            #[cfg(feature = "snapshot0")]
             Self::Snapshot0(wasi) => wasi.add_to_linker(linker),
+            #[cfg(feature = "snapshot1")]
             Self::Snapshot1(wasi) => wasi.add_to_linker(linker),
-        }
+        };
+        result.map_err(WasiDetectError::Link)
+    }
+
+    /// Creates a [`Linker`](wasmtime::Linker) for the store with all instance items added.
+    ///
+    /// Other items can be defined in the returned linker, use
+    /// [`into_linker_with`](AutoWasi::into_linker_with) to add the instance to a linker that
+    /// already defines some.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # use wasmtime_wasi::*;
+    /// # fn test(module: &Module) -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasi = AutoWasi::new(&store, WasiCtx::new(std::env::args())?, WasiVersion::Snapshot1)?;
+    /// let instance = wasi.into_linker(&store)?.instantiate(module)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn into_linker(self, store: &Store) -> Result<Linker, WasiDetectError> {
+        self.into_linker_with(Linker::new(store))
+    }
+
+    /// Adds all instance items to the linker, returning it.
+    ///
+    /// Fails like [`add_to_linker`](AutoWasi::add_to_linker), such as when the linker already
+    /// defines one of the functions.
+    pub fn into_linker_with(self, mut linker: Linker) -> Result<Linker, WasiDetectError> {
+        self.add_to_linker(&mut linker)?;
+        Ok(linker)
+    }
+}
+
+/// Only prints the version, the instance's functions and context aren't `Debug` and the context
+/// may hold the module's args and environment.
+impl fmt::Debug for AutoWasi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AutoWasi")
+            .field("version", &self.version())
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<&AutoWasi> for WasiVersion {
+    fn from(wasi: &AutoWasi) -> Self {
+        wasi.version()
     }
 }
 
 /// The version of WASI that a binary relies on.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// New versions may be added in minor releases. Hashes are only consistent with `Eq` within a
+/// release, hash values may change when versions are added and shouldn't be persisted.
+///
+/// Each version also has a stable integer code, returned by
+/// [`abi_version`](WasiVersion::abi_version) and converted back with `TryFrom<u32>`:
+///
+/// | Version     | Code |
+/// |-------------|------|
+/// | `Snapshot0` | 0    |
+/// | `Snapshot1` | 1    |
+/// | `Preview2`  | 2    |
+/// | `Wasix32v1` | 3    |
+/// | `Wasix64v1` | 4    |
+///
+/// Codes are never reassigned, new versions get the next unused one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum WasiVersion {
     /// Called `wasi_unstable` in binaries.
     Snapshot0,
     /// Called `wasi_snapshot_preview1` in binaries.
     Snapshot1,
+    /// WASI 0.2, component-model interfaces prefixed with `wasi:` in binaries, such as
+    /// `wasi:cli/stdout`.
+    ///
+    /// Components are detected as this version if any of their core modules import WASI, as the
+    /// `wasi_snapshot_preview1` imports of modules in components are satisfied by the preview1
+    /// adapter on top of these interfaces.
+    Preview2,
+    /// Called `wasix_32v1` in binaries, WASIX extensions to `wasi_snapshot_preview1` for 32-bit
+    /// memories.
+    Wasix32v1,
+    /// Called `wasix_64v1` in binaries, WASIX extensions to `wasi_snapshot_preview1` for 64-bit
+    /// memories.
+    Wasix64v1,
 }
 
 impl WasiVersion {
+    /// Every version, from oldest to newest.
+    const VERSIONS: [WasiVersion; 5] = [
+        Self::Snapshot0,
+        Self::Snapshot1,
+        Self::Wasix32v1,
+        Self::Wasix64v1,
+        Self::Preview2,
+    ];
+
+    /// Returns the index of this version in [`VERSIONS`](Self::VERSIONS).
+    ///
+    /// The match has no wildcard, so adding a version fails to compile until it's given the next
+    /// index here. The version then has to be appended to `VERSIONS` too, which is checked to
+    /// list every version at its index.
+    pub(crate) const fn release(self) -> usize {
+        match self {
+            Self::Snapshot0 => 0,
+            Self::Snapshot1 => 1,
+            Self::Wasix32v1 => 2,
+            Self::Wasix64v1 => 3,
+            Self::Preview2 => 4,
+        }
+    }
+
+    /// Returns every version, from oldest to newest.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// let names: Vec<_> = WasiVersion::all().iter().map(|v| v.to_string()).collect();
+    /// assert_eq!(names[..2], ["snapshot0", "snapshot1"]);
+    /// ```
+    pub fn all() -> &'static [WasiVersion] {
+        &Self::VERSIONS
+    }
+
+    /// The newest version an [`AutoWasi`](crate::AutoWasi) can be created for, which binaries
+    /// without WASI imports are detected as by default when their exports don't hint at a
+    /// version.
+    pub const LATEST: WasiVersion = WasiVersion::Snapshot1;
+
+    /// Returns whether this version is the same as or newer than the other, following the
+    /// [release order](#impl-Ord-for-WasiVersion).
+    pub fn is_at_least(&self, other: WasiVersion) -> bool {
+        *self >= other
+    }
+
+    /// Returns the stable integer code of this version, see the [type docs](WasiVersion).
+    pub fn abi_version(&self) -> u32 {
+        match self {
+            Self::Snapshot0 => 0,
+            Self::Snapshot1 => 1,
+            Self::Preview2 => 2,
+            Self::Wasix32v1 => 3,
+            Self::Wasix64v1 => 4,
+        }
+    }
+
+    /// Returns the name of the module this version's functions are imported from.
+    ///
+    /// For [`WasiVersion::Preview2`](crate::WasiVersion::Preview2) this is the `wasi:` prefix shared
+    /// by all of its interface names.
+    pub fn module_name(&self) -> &'static str {
+        match self {
+            Self::Snapshot0 => "wasi_unstable",
+            Self::Snapshot1 => "wasi_snapshot_preview1",
+            Self::Preview2 => "wasi:",
+            Self::Wasix32v1 => "wasix_32v1",
+            Self::Wasix64v1 => "wasix_64v1",
+        }
+    }
+
+    /// Returns the version whose functions are imported from the provided module name, if any.
+    pub fn from_module_name(name: &str) -> Option<Self> {
+        [
+            Self::Snapshot0,
+            Self::Snapshot1,
+            Self::Wasix32v1,
+            Self::Wasix64v1,
+        ]
+        .iter()
+        .find(|version| name == version.module_name())
+        .copied()
+        .or_else(|| {
+            if name.starts_with(Self::Preview2.module_name()) {
+                Some(Self::Preview2)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns whether the import is from a module named like a WASI version, such as a newer
+    /// snapshot, that isn't a version or extension this release knows.
+    pub(crate) fn is_unknown_module(module: &str, name: &str) -> bool {
+        module.starts_with("wasi")
+            && Self::from_module_name(module).is_none()
+            && WasiExtension::from_import(module, name).is_none()
+    }
+
+    /// Returns whether a binary using this version can be hosted by a runtime providing the
+    /// host version.
+    ///
+    /// Currently only the same version is compatible, none of the versions provide the imports
+    /// of another.
+    pub fn compatible_with(&self, host: WasiVersion) -> bool {
+        *self == host
+    }
+
     /// Detects the WASI version used by the binary, defaults to the latest.
-    pub fn detect<T: AsRef<[u8]>>(binary: T) -> Result<Self> {
-        for payload in Parser::new(0).parse_all(binary.as_ref()) {
-            match payload? {
-                Payload::ImportSection(reader) => {
-                    for import in reader {
-                        if import?.module == "wasi_unstable" {
-                            return Ok(Self::Snapshot0);
-                        }
-                    }
-                }
-                _ => {}
+    ///
+    /// The binary is only parsed up until the end of its import section, unless it has no WASI
+    /// imports. The version of those is guessed from their exports when they follow a known ABI,
+    /// see [`DetectionConfig::export_heuristics`](crate::DetectionConfig::export_heuristics). Use
+    /// [`WasiVersion::detect_strict`](crate::WasiVersion::detect_strict) to reject binaries
+    /// without WASI imports instead.
+    ///
+    /// Fails with [`WasiDetectError::MixedVersions`](crate::WasiDetectError::MixedVersions) if the
+    /// binary imports from more than one WASI version, and with
+    /// [`WasiDetectError::UnknownWasiModule`](crate::WasiDetectError::UnknownWasiModule) rather
+    /// than defaulting if it only imports from modules named like an unknown WASI version, such as
+    /// `wasi_snapshot_preview2`.
+    ///
+    /// With the `tracing` feature, detection runs in a `detect` span at the debug level, recording
+    /// the binary's length and the detected version.
+    pub fn detect<T: AsRef<[u8]>>(binary: T) -> Result<Self, WasiDetectError> {
+        let binary = binary.as_ref();
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "detect",
+            binary.len = binary.len(),
+            version = tracing::field::Empty
+        )
+        .entered();
+
+        let result = Self::detect_with_config(binary, &DetectionConfig::default());
+        #[cfg(feature = "tracing")]
+        {
+            if let Ok(version) = &result {
+                span.record("version", tracing::field::display(version));
             }
         }
+        result
+    }
+
+    /// Detects the WASI version used by the binary like
+    /// [`WasiVersion::detect`](crate::WasiVersion::detect), without allocating.
+    ///
+    /// Returns `None` wherever `detect` would fail, without saying why, including binaries
+    /// exceeding the [default](crate::DetectLimits::default) limits. Text format modules aren't
+    /// converted and return `None` too.
+    ///
+    /// Only detection doesn't allocate, the crate still depends on `std`.
+    pub fn detect_no_alloc(binary: &[u8]) -> Option<Self> {
+        no_alloc::detect(binary)
+    }
+
+    /// Detects the WASI version used by the binary like
+    /// [`WasiVersion::detect`](crate::WasiVersion::detect), within the provided limits instead of
+    /// the default ones.
+    ///
+    /// Fails with [`WasiDetectError::LimitExceeded`](crate::WasiDetectError::LimitExceeded) once
+    /// any of the limits is exceeded, without parsing the rest of the binary.
+    pub fn detect_with_limits<T: AsRef<[u8]>>(
+        binary: T,
+        limits: &DetectLimits,
+    ) -> Result<Self, WasiDetectError> {
+        let config = DetectionConfig {
+            limits: *limits,
+            ..DetectionConfig::default()
+        };
+        Self::detect_with_config(binary, &config)
+    }
+
+    /// Detects the WASI version used by the binary on a best-effort basis, returning `None` if
+    /// none can be detected.
+    ///
+    /// Unlike [`WasiVersion::try_detect`](crate::WasiVersion::try_detect) this never fails,
+    /// detecting the version from the imports read before a malformed section or import and
+    /// returning a [`WasiDetectWarning`](crate::WasiDetectWarning) for what went wrong. Meant for
+    /// tooling that processes corrupted or hand-crafted binaries, [`DetectLimits`](crate::DetectLimits)
+    /// aren't applied and text format modules aren't converted.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let mut wasm = wat::parse_str(r#"(module (import "wasi_unstable" "proc_exit" (func)))"#)?;
+    /// // Truncate the binary in the import's name.
+    /// wasm.truncate(wasm.len() - 4);
+    /// let (version, warnings) = WasiVersion::detect_resilient(&wasm);
+    /// assert_eq!(version, None);
+    /// assert!(!warnings.is_empty());
+    /// # Ok(()) }
+    /// ```
+    pub fn detect_resilient(binary: &[u8]) -> (Option<Self>, Vec<WasiDetectWarning>) {
+        let mut warnings = Vec::new();
+        let imports = WasiImports::parse_resilient(binary, &mut warnings);
+        let version = match imports.version() {
+            Ok(version) => version,
+            Err(error) => {
+                warnings.push(WasiDetectWarning::MixedVersions(error));
+                None
+            }
+        };
+        (version, warnings)
+    }
+
+    /// Detects the WASI version used by the binary as configured, see
+    /// [`DetectionConfig`](crate::DetectionConfig).
+    ///
+    /// The other detection functions such as
+    /// [`WasiVersion::detect_strict`](crate::WasiVersion::detect_strict) and
+    /// [`WasiVersion::detect_with_fallback`](crate::WasiVersion::detect_with_fallback) are
+    /// shorthands for a single option.
+    pub fn detect_with_config<T: AsRef<[u8]>>(
+        binary: T,
+        config: &DetectionConfig,
+    ) -> Result<Self, WasiDetectError> {
+        WasiImports::parse_with_limits(binary.as_ref(), &config.limits)?.configured_version(config)
+    }
+
+    /// Detects the WASI version used by the binary embedded in `data` at `offset`, spanning
+    /// `length` bytes.
+    ///
+    /// Fails with [`WasiDetectError::InvalidRange`](crate::WasiDetectError::InvalidRange) if the
+    /// range doesn't fit in `data`. Offsets in parse errors are relative to the embedded binary.
+    pub fn detect_from_bytes_at_offset(
+        data: &[u8],
+        offset: usize,
+        length: usize,
+    ) -> Result<Self, WasiDetectError> {
+        let binary = offset
+            .checked_add(length)
+            .and_then(|end| data.get(offset..end))
+            .ok_or(WasiDetectError::InvalidRange {
+                offset,
+                length,
+                len: data.len(),
+            })?;
+        Self::detect(binary)
+    }
+
+    /// Detects the WASI version used by the binary, defaults to the fallback.
+    ///
+    /// Unlike [`WasiVersion::detect`](crate::WasiVersion::detect), the version of binaries without
+    /// WASI imports isn't guessed from their exports, they're always detected as the fallback.
+    ///
+    /// Fails with [`WasiDetectError::MixedVersions`](crate::WasiDetectError::MixedVersions) if the
+    /// binary imports from more than one WASI version.
+    pub fn detect_with_fallback<T: AsRef<[u8]>>(
+        binary: T,
+        fallback: WasiVersion,
+    ) -> Result<Self, WasiDetectError> {
+        let config = DetectionConfig {
+            fallback,
+            export_heuristics: false,
+            ..DetectionConfig::default()
+        };
+        Self::detect_with_config(binary, &config)
+    }
 
-        Ok(Self::default())
+    /// Detects the WASI version used by the binary, returning `None` if it doesn't import any
+    /// WASI version.
+    ///
+    /// Fails with [`WasiDetectError::MixedVersions`](crate::WasiDetectError::MixedVersions) if the
+    /// binary imports from more than one WASI version.
+    pub fn try_detect<T: AsRef<[u8]>>(binary: T) -> Result<Option<Self>, WasiDetectError> {
+        WasiImports::parse(binary.as_ref())?.detected_version()
+    }
+
+    /// Detects the WASI version used by an already compiled module, defaults to the latest.
+    ///
+    /// This applies the same rules to [`Module::imports`](wasmtime::Module::imports) as
+    /// [`WasiVersion::detect`](crate::WasiVersion::detect) does to the binary's import section.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # use wasmtime::*;
+    /// # fn test() -> anyhow::Result<()> {
+    /// let engine = Engine::default();
+    /// let module = Module::new(&engine, r#"(module (import "wasi_unstable" "sched_yield" (func)))"#)?;
+    /// // The binary is no longer needed once the module is compiled.
+    /// assert_eq!(WasiVersion::detect_from_module(&module)?, WasiVersion::Snapshot0);
+    /// # Ok(()) }
+    /// ```
+    pub fn detect_from_module(module: &Module) -> Result<Self, WasiDetectError> {
+        let imports = module
+            .imports()
+            .map(|import| (import.module(), import.name().unwrap_or_default()));
+        Ok(Self::detect_from_imports(imports)?.unwrap_or_default())
+    }
+
+    /// Detects the WASI version implied by the module and field names of a binary's imports,
+    /// returning `None` if none of them are from a WASI version.
+    ///
+    /// This applies the same rules as [`WasiVersion::try_detect`](crate::WasiVersion::try_detect),
+    /// for import lists that are already known without a binary to parse. Fails with
+    /// [`WasiDetectError::MixedVersions`](crate::WasiDetectError::MixedVersions) for more than one
+    /// WASI version, and with
+    /// [`WasiDetectError::UnknownWasiModule`](crate::WasiDetectError::UnknownWasiModule) if the
+    /// imports are only from modules named like an unknown WASI version.
+    ///
+    /// ```rust
+    /// # use auto_wasi::*;
+    /// # fn test() -> Result<(), WasiDetectError> {
+    /// let imports = [("env", "log"), ("wasi_unstable", "fd_write")];
+    /// assert_eq!(
+    ///     WasiVersion::detect_from_imports(imports.iter().cloned())?,
+    ///     Some(WasiVersion::Snapshot0)
+    /// );
+    /// # Ok(()) }
+    /// ```
+    pub fn detect_from_imports<'a, I>(imports: I) -> Result<Option<Self>, WasiDetectError>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut wasi = WasiImports::default();
+        for (module, name) in imports {
+            // The kind is only reported for unresolved imports, which the unknown module check
+            // doesn't look at.
+            wasi.add_import(module, name, ImportKind::Other, None);
+        }
+        wasi.detected_version()
+    }
+
+    /// Detects the WASI version used by a module serialized with
+    /// [`Module::serialize`](wasmtime::Module::serialize).
+    ///
+    /// Fails with [`WasiDetectError::Deserialize`](crate::WasiDetectError::Deserialize) if the
+    /// artifact can't be deserialized for the engine, and with
+    /// [`WasiDetectError::NoWasiImports`](crate::WasiDetectError::NoWasiImports) if the module
+    /// doesn't import any WASI version.
+    pub fn detect_precompiled(engine: &Engine, serialized: &[u8]) -> Result<Self, WasiDetectError> {
+        let module =
+            Module::deserialize(engine, serialized).map_err(WasiDetectError::Deserialize)?;
+        let mut imports = WasiImports::default();
+        imports.add_module(&module);
+        imports.strict_version()
+    }
+
+    /// Detects the WASI version used by each core module embedded in the component, returning
+    /// `None` for modules that don't import any WASI version.
+    ///
+    /// Modules are indexed in the order they appear in the binary, with the modules of nested
+    /// components in place of the component. Core module binaries are treated as a component
+    /// containing just that module.
+    pub fn detect_in_component<T: AsRef<[u8]>>(
+        binary: T,
+    ) -> Result<Vec<(usize, Option<Self>)>, WasiDetectError> {
+        let binary = binary.as_ref();
+        if !component::is_component(binary) {
+            return Ok(vec![(0, Self::try_detect(binary)?)]);
+        }
+
+        component::core_modules(binary)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, module)| Ok((index, WasiImports::parse(module)?.detected_version()?)))
+            .collect()
+    }
+
+    /// Collects every WASI version the binary imports from.
+    ///
+    /// Unlike [`WasiVersion::detect`](crate::WasiVersion::detect) this doesn't fail for binaries
+    /// importing from more than one WASI version, leaving the decision to the caller.
+    pub fn detect_all<T: AsRef<[u8]>>(binary: T) -> Result<WasiVersionSet, WasiDetectError> {
+        Ok(WasiImports::parse(binary.as_ref())?.versions())
+    }
+
+    /// Lists the binary's imports from experimental WASI extensions, such as wasi-nn, grouped by
+    /// module in the order they first appear.
+    ///
+    /// An [`AutoWasi`](crate::AutoWasi) doesn't provide any of these, so a binary importing them
+    /// can't be linked with one alone.
+    pub fn detect_extensions<T: AsRef<[u8]>>(
+        binary: T,
+    ) -> Result<Vec<ExtensionImports>, WasiDetectError> {
+        Ok(WasiImports::parse(binary.as_ref())?.extensions)
+    }
+
+    /// Infers the capabilities the binary needs from its `wasi_unstable` and
+    /// `wasi_snapshot_preview1` imports.
+    pub fn required_capabilities<T: AsRef<[u8]>>(
+        binary: T,
+    ) -> Result<RequiredCapabilities, WasiDetectError> {
+        Ok(WasiImports::parse(binary.as_ref())?.capabilities())
+    }
+
+    /// Detects the WASI version used by the binary and lists all of its WASI imports.
+    ///
+    /// Fails with [`WasiDetectError::MixedVersions`](crate::WasiDetectError::MixedVersions) if the
+    /// binary imports from more than one WASI version.
+    pub fn inspect<T: AsRef<[u8]>>(binary: T) -> Result<DetectionReport, WasiDetectError> {
+        WasiImports::parse(binary.as_ref())?.report()
+    }
+
+    /// Detects the WASI version used by the binary like
+    /// [`WasiVersion::detect`](crate::WasiVersion::detect), failing with
+    /// [`WasiDetectError::UnknownFunctions`](crate::WasiDetectError::UnknownFunctions) if it
+    /// imports functions the version doesn't define.
+    ///
+    /// Such binaries usually target a fork of WASI and can't be linked with an
+    /// [`AutoWasi`](crate::AutoWasi).
+    pub fn detect_known<T: AsRef<[u8]>>(binary: T) -> Result<Self, WasiDetectError> {
+        let config = DetectionConfig {
+            reject_unknown_functions: true,
+            ..DetectionConfig::default()
+        };
+        Self::detect_with_config(binary, &config)
+    }
+
+    /// Detects the WASI version used by the binary, failing with
+    /// [`WasiDetectError::NoWasiImports`](crate::WasiDetectError::NoWasiImports) if it doesn't import
+    /// any WASI version.
+    pub fn detect_strict<T: AsRef<[u8]>>(binary: T) -> Result<Self, WasiDetectError> {
+        let config = DetectionConfig {
+            strict: true,
+            ..DetectionConfig::default()
+        };
+        Self::detect_with_config(binary, &config)
+    }
+
+    /// Detects the WASI version used by the binary read from the reader, defaults to the latest.
+    ///
+    /// The binary is parsed as it's read, and reading stops at the end of its import section, so
+    /// unlike [`WasiVersion::detect`](crate::WasiVersion::detect) exports aren't used to guess the
+    /// version of binaries without WASI imports, and text format modules aren't converted.
+    ///
+    /// Fails with [`WasiDetectError::Truncated`](crate::WasiDetectError::Truncated) if the reader
+    /// ends before that.
+    pub fn detect_from_reader<R: Read>(reader: R) -> Result<Self, WasiDetectError> {
+        Ok(WasiImports::read(reader)?
+            .detected_version()?
+            .unwrap_or_default())
+    }
+
+    /// Detects the WASI version used by the text format module, defaults to the latest.
+    ///
+    /// The functions taking the whole binary, such as
+    /// [`WasiVersion::detect`](crate::WasiVersion::detect),
+    /// [`WasiVersion::inspect`](crate::WasiVersion::inspect),
+    /// [`WasiVersion::validate`](crate::WasiVersion::validate) and
+    /// [`WasiVersion::detect_from_path`](crate::WasiVersion::detect_from_path), also accept text
+    /// starting with `(module`. The ones that don't get the whole module at once don't:
+    /// [`WasiVersion::detect_from_reader`](crate::WasiVersion::detect_from_reader),
+    /// [`WasiVersionDetector`](crate::WasiVersionDetector),
+    /// [`WasiVersion::detect_no_alloc`](crate::WasiVersion::detect_no_alloc) and
+    /// [`WasiVersion::detect_resilient`](crate::WasiVersion::detect_resilient) fail to parse it.
+    #[cfg(feature = "wat")]
+    pub fn detect_wat(wat: &str) -> Result<Self, WasiDetectError> {
+        Self::detect(wat::parse_str(wat)?)
+    }
+
+    /// Checks the signatures of the binary's WASI imports against the ones of the detected version.
+    ///
+    /// Returns every imported function whose signature differs, imports of functions the version
+    /// doesn't provide are ignored.
+    pub fn validate<T: AsRef<[u8]>>(binary: T) -> Result<Vec<SignatureMismatch>, WasiDetectError> {
+        WasiImports::parse(binary.as_ref())?.validate()
+    }
+
+    /// Detects the WASI version used by the binary at the provided path, defaults to the latest.
+    ///
+    /// The file's contents are detected like [`WasiVersion::detect`](crate::WasiVersion::detect),
+    /// so the result doesn't depend on the features enabled. The file is read into memory, or
+    /// memory-mapped with the `mmap` feature, falling back to reading it if it can't be mapped. Use
+    /// [`WasiVersion::detect_from_reader`](crate::WasiVersion::detect_from_reader) to only read a
+    /// large file up until the end of its import section.
+    pub fn detect_from_path<P: AsRef<Path>>(path: P) -> Result<Self, WasiDetectError> {
+        let path = path.as_ref();
+        File::open(path)
+            .map_err(WasiDetectError::from)
+            .and_then(Self::detect_from_file)
+            .map_err(|error| WasiDetectError::Path {
+                path: path.to_path_buf(),
+                error: Box::new(error),
+            })
+    }
+
+    /// Detects the WASI version used by each binary, in the same order.
+    ///
+    /// Each binary is detected on its own, so a binary failing to parse doesn't affect the
+    /// others. With the `parallel` feature the binaries are detected concurrently on rayon's
+    /// global thread pool.
+    pub fn detect_batch<I, T>(binaries: I) -> Vec<Result<Self, WasiDetectError>>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]> + Send,
+    {
+        map_each(binaries.into_iter().collect(), Self::detect)
+    }
+
+    /// Detects the WASI version used by the binary at each path, in the same order.
+    ///
+    /// Like [`WasiVersion::detect_batch`](crate::WasiVersion::detect_batch) but reading each
+    /// binary with [`WasiVersion::detect_from_path`](crate::WasiVersion::detect_from_path), so
+    /// every error is a [`WasiDetectError::Path`](crate::WasiDetectError::Path) naming its path.
+    pub fn detect_batch_paths<I, P>(paths: I) -> Vec<Result<Self, WasiDetectError>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path> + Send,
+    {
+        map_each(paths.into_iter().collect(), Self::detect_from_path)
+    }
+
+    /// Detects the file's contents like [`detect`](Self::detect), whether they're mapped or read.
+    fn detect_from_file(mut file: File) -> Result<Self, WasiDetectError> {
+        #[cfg(feature = "mmap")]
+        {
+            // Safety: the mapping only lives for the duration of detection, a file truncated in
+            // the meantime is the same hazard every other mmap user accepts.
+            if let Ok(map) = unsafe { memmap2::Mmap::map(&file) } {
+                return Self::detect(&*map);
+            }
+        }
+
+        let mut binary = Vec::new();
+        file.read_to_end(&mut binary)?;
+        Self::detect(binary)
     }
 }
 
+/// Defaults to [`WasiVersion::LATEST`](crate::WasiVersion::LATEST).
 impl Default for WasiVersion {
     fn default() -> Self {
-        Self::Snapshot1
+        Self::LATEST
+    }
+}
+
+// Every version is in `VERSIONS` at its release index.
+const _: () = {
+    let mut index = 0;
+    while index < WasiVersion::VERSIONS.len() {
+        assert!(WasiVersion::VERSIONS[index].release() == index);
+        index += 1;
+    }
+};
+
+/// Versions are ordered by when they were released, so
+/// `Snapshot0 < Snapshot1 < Wasix32v1 < Wasix64v1 < Preview2`.
+///
+/// The order of existing versions never changes, newer versions are greater than all the
+/// versions before them.
+impl Ord for WasiVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.release().cmp(&other.release())
+    }
+}
+
+impl PartialOrd for WasiVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Formats the version as `snapshot0`, `snapshot1`, `preview2`, `wasix32v1` or `wasix64v1`.
+impl fmt::Display for WasiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Snapshot0 => write!(f, "snapshot0"),
+            Self::Snapshot1 => write!(f, "snapshot1"),
+            Self::Preview2 => write!(f, "preview2"),
+            Self::Wasix32v1 => write!(f, "wasix32v1"),
+            Self::Wasix64v1 => write!(f, "wasix64v1"),
+        }
+    }
+}
+
+/// Parses the version from its [`Display`](std::fmt::Display) name, such as `snapshot1`, from
+/// the name of the module it's imported from, such as `wasi_snapshot_preview1`, or from one of the
+/// aliases `preview1`, `wasip1` and `wasip2` used by toolchains.
+impl std::str::FromStr for WasiVersion {
+    type Err = ParseWasiVersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const ALIASES: [(&str, WasiVersion); 3] = [
+            ("preview1", WasiVersion::Snapshot1),
+            ("wasip1", WasiVersion::Snapshot1),
+            ("wasip2", WasiVersion::Preview2),
+        ];
+
+        Self::VERSIONS
+            .iter()
+            .find(|version| version.to_string() == s)
+            .or_else(|| {
+                ALIASES
+                    .iter()
+                    .find(|(alias, _)| *alias == s)
+                    .map(|(_, v)| v)
+            })
+            .copied()
+            .or_else(|| Self::from_module_name(s))
+            .ok_or_else(|| ParseWasiVersionError {
+                input: s.to_string(),
+            })
+    }
+}
+
+/// Converts like [`FromStr`](std::str::FromStr).
+impl TryFrom<&str> for WasiVersion {
+    type Error = ParseWasiVersionError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Converts like [`FromStr`](std::str::FromStr).
+impl TryFrom<String> for WasiVersion {
+    type Error = ParseWasiVersionError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Converts the stable integer code returned by
+/// [`WasiVersion::abi_version`](crate::WasiVersion::abi_version) back to the version.
+impl TryFrom<u32> for WasiVersion {
+    type Error = UnknownWasiVersion;
+
+    fn try_from(code: u32) -> Result<Self, Self::Error> {
+        Self::VERSIONS
+            .iter()
+            .find(|version| version.abi_version() == code)
+            .copied()
+            .ok_or(UnknownWasiVersion(code))
+    }
+}
+
+/// Serializes the version as the name of the module it's imported from, such as
+/// `wasi_snapshot_preview1`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for WasiVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.module_name())
+    }
+}
+
+/// Deserializes the version from any string [`FromStr`](std::str::FromStr) accepts, failing for
+/// versions this release doesn't know.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for WasiVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Chooses one of [`WasiVersion::all`](crate::WasiVersion::all) uniformly, for fuzzing and
+/// property tests.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for WasiVersion {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        u.choose(Self::all()).copied()
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1, Some(1))
+    }
+}
+
+/// The WASI imports found in a binary.
+#[derive(Default)]
+struct WasiImports {
+    /// The function types of the type section, `None` for the ones that aren't valid functions.
+    types: Vec<Option<FuncType>>,
+    /// Whether parsing the type section stopped early, leaving the remaining types unknown.
+    partial_types: bool,
+    imports: Vec<(WasiVersion, WasiImport)>,
+    unresolved: Vec<UnresolvedImports>,
+    extensions: Vec<ExtensionImports>,
+    /// The version guessed from the exports of binaries without WASI imports.
+    export_version: Option<WasiVersion>,
+    limits: DetectLimits,
+}
+
+impl WasiImports {
+    fn parse(binary: &[u8]) -> Result<Self, WasiDetectError> {
+        Self::parse_with_limits(binary, &DetectLimits::default())
+    }
+
+    fn parse_with_limits(binary: &[u8], limits: &DetectLimits) -> Result<Self, WasiDetectError> {
+        let binary = text_to_binary(binary)?;
+        if component::is_component(&binary) {
+            return Self::parse_component(&binary, limits);
+        }
+        let mut imports = Self {
+            limits: *limits,
+            ..Self::default()
+        };
+
+        for payload in Parser::new(0).parse_all(&binary) {
+            if !imports.visit(payload?)? {
+                break;
+            }
+        }
+
+        if imports.imports.is_empty() {
+            imports.export_version = exports::guess(&binary, limits.max_bytes);
+        }
+        Ok(imports)
+    }
+
+    /// Collects the imports like [`parse`](Self::parse), recording malformed sections and imports
+    /// as warnings instead of failing.
+    fn parse_resilient(binary: &[u8], warnings: &mut Vec<WasiDetectWarning>) -> Self {
+        let mut imports = Self::default();
+        if component::is_component(binary) {
+            let modules = match component::core_modules(binary) {
+                Ok(modules) => modules,
+                Err(error) => {
+                    warnings.push(WasiDetectWarning::MalformedSection {
+                        offset: error.offset(),
+                        error,
+                    });
+                    return imports;
+                }
+            };
+            for module in modules {
+                let module = Self::parse_resilient(module, warnings);
+                for (_, import) in module.imports {
+                    imports.imports.push((WasiVersion::Preview2, import));
+                }
+            }
+            return imports;
+        }
+
+        for payload in Parser::new(0).parse_all(binary) {
+            match payload {
+                Ok(Payload::Version { .. }) | Ok(Payload::CustomSection { .. }) => {}
+                Ok(Payload::TypeSection(reader)) => imports.add_types(reader),
+                Ok(Payload::ImportSection(reader)) => {
+                    imports.add_resilient(reader, warnings);
+                    break;
+                }
+                Ok(_) => break,
+                Err(error) => {
+                    warnings.push(WasiDetectWarning::MalformedSection {
+                        offset: error.offset(),
+                        error,
+                    });
+                    break;
+                }
+            }
+        }
+        imports
+    }
+
+    /// Collects the WASI imports of every core module in the component as
+    /// [`WasiVersion::Preview2`](crate::WasiVersion::Preview2) imports.
+    ///
+    /// Fails if none of the modules import WASI, as there's nothing to classify the component by.
+    fn parse_component(binary: &[u8], limits: &DetectLimits) -> Result<Self, WasiDetectError> {
+        DetectLimits::check("max_bytes", limits.max_bytes, binary.len())?;
+
+        let mut imports = Self::default();
+        for module in component::core_modules(binary)? {
+            let module = Self::parse_with_limits(module, limits)?;
+            for (_, import) in module.imports {
+                imports.imports.push((WasiVersion::Preview2, import));
+            }
+            for unresolved in module.unresolved {
+                for import in unresolved.imports {
+                    imports.add_unresolved(&unresolved.module, import);
+                }
+            }
+            for extension in module.extensions {
+                for name in &extension.names {
+                    imports.add_extension(extension.extension.clone(), &extension.module, name);
+                }
+            }
+        }
+
+        if imports.imports.is_empty() {
+            return Err(WasiDetectError::Component);
+        }
+        Ok(imports)
+    }
+
+    fn read<R: Read>(mut reader: R) -> Result<Self, WasiDetectError> {
+        const CHUNK_SIZE: u64 = 64 * 1024;
+
+        let mut imports = Self::default();
+        let mut parser = Parser::new(0);
+        let mut buf = Vec::new();
+        let mut offset = 0;
+        let mut eof = false;
+
+        loop {
+            let chunk = match parser.parse(&buf, eof) {
+                Ok(chunk) => chunk,
+                // Components are read whole to find their modules.
+                Err(_) if component::is_component(&buf) => {
+                    let limits = imports.limits;
+                    let remaining = limits.max_bytes.saturating_sub(buf.len()) as u64;
+                    reader.take(remaining + 1).read_to_end(&mut buf)?;
+                    return Self::parse_component(&buf, &limits);
+                }
+                // The parser only fails at the end of the binary after asking for more data.
+                Err(_) if eof => return Err(WasiDetectError::Truncated { offset }),
+                Err(error) => return Err(error.into()),
+            };
+            let (payload, consumed) = match chunk {
+                Chunk::NeedMoreData(hint) => {
+                    let limits = &imports.limits;
+                    DetectLimits::check("max_bytes", limits.max_bytes, offset + hint as usize)?;
+                    let len = buf.len();
+                    buf.resize(len + hint.min(CHUNK_SIZE) as usize, 0);
+                    let read = reader.read(&mut buf[len..])?;
+                    buf.truncate(len + read);
+                    offset += read;
+                    eof = read == 0;
+                    continue;
+                }
+                Chunk::Parsed { consumed, payload } => (payload, consumed),
+            };
+
+            if !imports.visit(payload)? {
+                break;
+            }
+
+            buf.drain(..consumed);
+        }
+
+        Ok(imports)
+    }
+
+    /// Collects the imports of the payload, returning whether later payloads could contain more.
+    fn visit(&mut self, payload: Payload) -> Result<bool, WasiDetectError> {
+        let max_bytes = self.limits.max_bytes;
+        match payload {
+            Payload::Version { .. } => Ok(true),
+            Payload::CustomSection {
+                data_offset, data, ..
+            } => {
+                DetectLimits::check("max_bytes", max_bytes, data_offset + data.len())?;
+                Ok(true)
+            }
+            Payload::TypeSection(reader) => {
+                DetectLimits::check("max_bytes", max_bytes, reader.range().end)?;
+                self.add_types(reader);
+                Ok(true)
+            }
+            Payload::ImportSection(reader) => {
+                DetectLimits::check("max_bytes", max_bytes, reader.range().end)?;
+                self.add(reader)?;
+                Ok(false)
+            }
+            // Imports must come before every other section, so there are none left to find.
+            _ => Ok(false),
+        }
+    }
+
+    /// Collects the function types of the section.
+    ///
+    /// Detection only needs the types to validate signatures, so types encoded with proposals
+    /// the parser doesn't know about, such as GC, end the collection instead of failing.
+    fn add_types(&mut self, reader: TypeSectionReader) {
+        for ty in reader {
+            let ty = match ty {
+                Ok(TypeDef::Func(ty)) => func_type(&ty),
+                Ok(_) => None,
+                Err(_) => {
+                    self.partial_types = true;
+                    return;
+                }
+            };
+            self.types.push(ty);
+        }
+    }
+
+    fn add(&mut self, reader: ImportSectionReader) -> Result<(), WasiDetectError> {
+        let limits = self.limits;
+        DetectLimits::check(
+            "max_imports",
+            limits.max_imports,
+            reader.get_count() as usize,
+        )?;
+
+        for import in reader {
+            let import = import?;
+            let name_len = import
+                .module
+                .len()
+                .max(import.field.unwrap_or_default().len());
+            DetectLimits::check("max_name_len", limits.max_name_len, name_len)?;
+            self.add_entry(import);
+        }
+
+        Ok(())
+    }
+
+    /// Collects the imports of the section like [`add`](Self::add) up until the first malformed
+    /// one, without any limits.
+    fn add_resilient(
+        &mut self,
+        reader: ImportSectionReader,
+        warnings: &mut Vec<WasiDetectWarning>,
+    ) {
+        for import in reader {
+            match import {
+                Ok(import) => self.add_entry(import),
+                // The reader can't find the start of the next entry after a malformed one.
+                Err(error) => {
+                    warnings.push(WasiDetectWarning::MalformedImport {
+                        offset: error.offset(),
+                        error,
+                    });
+                    return;
+                }
+            }
+        }
+    }
+
+    fn add_entry(&mut self, import: Import) {
+        let (kind, type_index) = match import.ty {
+            ImportSectionEntryType::Function(index) => (ImportKind::Func, Some(index)),
+            ImportSectionEntryType::Table(_) => (ImportKind::Table, None),
+            ImportSectionEntryType::Memory(_) => (ImportKind::Memory, None),
+            ImportSectionEntryType::Global(_) => (ImportKind::Global, None),
+            _ => (ImportKind::Other, None),
+        };
+        let name = import.field.unwrap_or_default();
+        self.add_import(import.module, name, kind, type_index);
+    }
+
+    fn add_module(&mut self, module: &Module) {
+        for import in module.imports() {
+            let name = import.name().unwrap_or_default();
+            self.add_import(import.module(), name, import_kind(&import.ty()), None);
+        }
+    }
+
+    fn add_import(&mut self, module: &str, name: &str, kind: ImportKind, type_index: Option<u32>) {
+        if let Some(extension) = WasiExtension::from_import(module, name) {
+            self.add_extension(extension, module, name);
+        }
+
+        let version = match WasiVersion::from_module_name(module) {
+            Some(version) => version,
+            None => {
+                let name = name.to_string();
+                self.add_unresolved(module, UnresolvedImport { name, kind });
+                return;
+            }
+        };
+
+        self.imports.push((
+            version,
+            WasiImport {
+                module: module.to_string(),
+                name: name.to_string(),
+                type_index,
+            },
+        ));
+    }
+
+    fn add_unresolved(&mut self, module: &str, import: UnresolvedImport) {
+        match self.unresolved.iter_mut().find(|u| u.module == module) {
+            Some(unresolved) => unresolved.imports.push(import),
+            None => self.unresolved.push(UnresolvedImports {
+                module: module.to_string(),
+                imports: vec![import],
+            }),
+        }
+    }
+
+    fn add_extension(&mut self, extension: WasiExtension, module: &str, name: &str) {
+        match self.extensions.iter_mut().find(|e| e.module == module) {
+            Some(imports) => imports.names.push(name.to_string()),
+            None => self.extensions.push(ExtensionImports {
+                extension,
+                module: module.to_string(),
+                names: vec![name.to_string()],
+            }),
+        }
+    }
+
+    fn versions(&self) -> WasiVersionSet {
+        self.imports.iter().map(|(version, _)| *version).collect()
     }
+
+    fn version(&self) -> Result<Option<WasiVersion>, MixedVersionsError> {
+        let mut versions: Vec<(WasiVersion, Vec<String>)> = Vec::new();
+        for (version, import) in &self.imports {
+            match versions.iter_mut().find(|(v, _)| v == version) {
+                Some((_, names)) => names.push(import.name.clone()),
+                None => versions.push((*version, vec![import.name.clone()])),
+            }
+        }
+
+        // WASIX binaries also import the `wasi_snapshot_preview1` functions WASIX extends.
+        if versions
+            .iter()
+            .any(|(v, _)| matches!(v, WasiVersion::Wasix32v1 | WasiVersion::Wasix64v1))
+        {
+            versions.retain(|(v, _)| v != &WasiVersion::Snapshot1);
+        }
+
+        match versions.len() {
+            0 => Ok(None),
+            1 => Ok(Some(versions.remove(0).0)),
+            _ => Err(MixedVersionsError { imports: versions }),
+        }
+    }
+
+    /// Returns the detected version like [`version`](Self::version), failing when no version is
+    /// imported but a module named like one is.
+    fn detected_version(&self) -> Result<Option<WasiVersion>, WasiDetectError> {
+        if let Some(version) = self.version()? {
+            return Ok(Some(version));
+        }
+
+        let unknown = self.unresolved.iter().find(|unresolved| {
+            unresolved
+                .imports
+                .iter()
+                .any(|import| WasiVersion::is_unknown_module(&unresolved.module, &import.name))
+        });
+        match unknown {
+            Some(unresolved) => Err(WasiDetectError::UnknownWasiModule {
+                module: unresolved.module.clone(),
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn capabilities(&self) -> RequiredCapabilities {
+        let mut capabilities = RequiredCapabilities::default();
+        for (version, import) in &self.imports {
+            if let WasiVersion::Snapshot0 | WasiVersion::Snapshot1 = version {
+                capabilities.add(&import.name);
+            }
+        }
+        capabilities
+    }
+
+    /// Returns the names of the imports of versions with known functions that aren't one of them.
+    fn unknown_functions(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (version, import) in &self.imports {
+            let known = abi::functions(version).is_empty()
+                || abi::function(version, &import.name).is_some();
+            if !known && !names.contains(&import.name) {
+                names.push(import.name.clone());
+            }
+        }
+        names
+    }
+
+    fn configured_version(&self, config: &DetectionConfig) -> Result<WasiVersion, WasiDetectError> {
+        let version = match config.unknown_namespaces {
+            UnknownNamespaceAction::Error => self.detected_version()?,
+            UnknownNamespaceAction::Ignore => self.version()?,
+        };
+        let version = match version {
+            Some(version) => version,
+            None if config.strict => {
+                return Err(WasiDetectError::NoWasiImports {
+                    namespaces: self.unresolved.iter().map(|u| u.module.clone()).collect(),
+                })
+            }
+            None if config.export_heuristics => self.export_version.unwrap_or(config.fallback),
+            None => config.fallback,
+        };
+
+        if config.reject_unknown_functions {
+            let names = self.unknown_functions();
+            if !names.is_empty() {
+                return Err(WasiDetectError::UnknownFunctions { version, names });
+            }
+        }
+        Ok(version)
+    }
+
+    fn report(self) -> Result<DetectionReport, WasiDetectError> {
+        let (version, source) = match self.version()? {
+            Some(version) => (Some(version), Some(VersionSource::Imports)),
+            None => match self.export_version {
+                Some(version) => (Some(version), Some(VersionSource::Exports)),
+                None => (None, None),
+            },
+        };
+        Ok(DetectionReport {
+            version,
+            source,
+            unknown_functions: self.unknown_functions(),
+            imports: self.imports.into_iter().map(|(_, import)| import).collect(),
+            extensions: self.extensions,
+            unresolved: self.unresolved,
+        })
+    }
+
+    fn validate(&self) -> Result<Vec<SignatureMismatch>, WasiDetectError> {
+        self.version()?;
+
+        let mut mismatches = Vec::new();
+        for (version, import) in &self.imports {
+            let function = match abi::function(version, &import.name) {
+                Some(function) => function,
+                None => continue,
+            };
+            let actual = match import.type_index {
+                Some(index) if index as usize >= self.types.len() && self.partial_types => continue,
+                Some(index) => self.types.get(index as usize).cloned().flatten(),
+                None => None,
+            };
+            let expected = function.ty();
+
+            if actual.as_ref() != Some(&expected) {
+                mismatches.push(SignatureMismatch {
+                    name: import.name.clone(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        Ok(mismatches)
+    }
+
+    fn strict_version(self) -> Result<WasiVersion, WasiDetectError> {
+        self.detected_version()?
+            .ok_or_else(|| WasiDetectError::NoWasiImports {
+                namespaces: self.unresolved.into_iter().map(|u| u.module).collect(),
+            })
+    }
+}
+
+/// Maps each item in order, on rayon's global thread pool with the `parallel` feature.
+#[cfg(feature = "parallel")]
+fn map_each<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Send + Sync,
+{
+    use rayon::prelude::*;
+    items.into_par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn map_each<T, R, F>(items: Vec<T>, f: F) -> Vec<R>
+where
+    F: Fn(T) -> R,
+{
+    items.into_iter().map(f).collect()
+}
+
+/// Converts text format modules, recognized by a leading `(module`, to binaries.
+#[cfg(feature = "wat")]
+pub(crate) fn text_to_binary(binary: &[u8]) -> Result<Cow<'_, [u8]>, WasiDetectError> {
+    let start = binary
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(binary.len());
+    if binary[start..].starts_with(b"(module") {
+        Ok(wat::parse_bytes(binary)?)
+    } else {
+        Ok(Cow::Borrowed(binary))
+    }
+}
+
+#[cfg(not(feature = "wat"))]
+pub(crate) fn text_to_binary(binary: &[u8]) -> Result<Cow<'_, [u8]>, WasiDetectError> {
+    Ok(Cow::Borrowed(binary))
+}
+
+fn import_kind(ty: &ExternType) -> ImportKind {
+    match ty {
+        ExternType::Func(_) => ImportKind::Func,
+        ExternType::Table(_) => ImportKind::Table,
+        ExternType::Memory(_) => ImportKind::Memory,
+        ExternType::Global(_) => ImportKind::Global,
+        _ => ImportKind::Other,
+    }
+}
+
+fn func_type(ty: &wasmparser::FuncType) -> Option<FuncType> {
+    fn val_type(ty: &wasmparser::Type) -> Option<ValType> {
+        match ty {
+            wasmparser::Type::I32 => Some(ValType::I32),
+            wasmparser::Type::I64 => Some(ValType::I64),
+            wasmparser::Type::F32 => Some(ValType::F32),
+            wasmparser::Type::F64 => Some(ValType::F64),
+            wasmparser::Type::V128 => Some(ValType::V128),
+            wasmparser::Type::FuncRef => Some(ValType::FuncRef),
+            wasmparser::Type::ExternRef => Some(ValType::ExternRef),
+            _ => None,
+        }
+    }
+
+    let params = ty.params.iter().map(val_type).collect::<Option<Vec<_>>>()?;
+    let results = ty
+        .returns
+        .iter()
+        .map(val_type)
+        .collect::<Option<Vec<_>>>()?;
+    Some(FuncType::new(params, results))
 }
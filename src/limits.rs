@@ -0,0 +1,42 @@
+use crate::WasiDetectError;
+
+/// Bounds on the work done detecting the WASI version of an untrusted binary.
+///
+/// Detection fails with [`WasiDetectError::LimitExceeded`](crate::WasiDetectError::LimitExceeded)
+/// as soon as any of them is exceeded. [`WasiVersion::detect`](crate::WasiVersion::detect) and
+/// the other detection functions use the [default](DetectLimits::default) limits, use
+/// [`WasiVersion::detect_with_limits`](crate::WasiVersion::detect_with_limits) to pick others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetectLimits {
+    /// The maximum number of entries in the import section, 10 000 by default.
+    pub max_imports: usize,
+    /// The maximum length in bytes of the module and field names of an import, 4 KiB by default.
+    pub max_name_len: usize,
+    /// The maximum number of bytes scanned, up until the end of the import section or the whole
+    /// binary for components, 16 MiB by default.
+    pub max_bytes: usize,
+}
+
+impl Default for DetectLimits {
+    fn default() -> Self {
+        Self {
+            max_imports: 10_000,
+            max_name_len: 4 * 1024,
+            max_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl DetectLimits {
+    /// Returns an error if the value exceeds the limit with the provided name.
+    pub(crate) fn check(
+        limit: &'static str,
+        max: usize,
+        value: usize,
+    ) -> Result<(), WasiDetectError> {
+        if value > max {
+            return Err(WasiDetectError::LimitExceeded { limit, max });
+        }
+        Ok(())
+    }
+}
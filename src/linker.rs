@@ -0,0 +1,13 @@
+/// What [`AutoWasi::add_to_linker_checked`](crate::AutoWasi::add_to_linker_checked) does with the
+/// WASI functions a [`Linker`](wasmtime::Linker) already defines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Conflict {
+    /// Fail with [`WasiDetectError::Conflicts`](crate::WasiDetectError::Conflicts) listing every
+    /// function the linker already defines, without adding any.
+    Error,
+    /// Keep the linker's definitions, only adding the functions it doesn't define yet.
+    KeepExisting,
+    /// Replace the linker's definitions with this instance's functions.
+    Overwrite,
+}
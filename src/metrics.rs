@@ -0,0 +1,132 @@
+//! Counting the WASI calls of guests.
+
+use crate::abi::{self, Function};
+use crate::{host, WasiVersion};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use wasi_common::WasiCtx;
+use wasmtime::Store;
+
+/// The number of calls to the WASI functions of an [`AutoWasi`](crate::AutoWasi) and the time
+/// spent in them.
+///
+/// Created by [`AutoWasi::with_metrics`](crate::AutoWasi::with_metrics). Clones share the
+/// counters, which keep counting as long as the instance is used.
+#[derive(Clone)]
+pub struct WasiMetrics {
+    functions: &'static [Function],
+    counters: Arc<[Counter]>,
+}
+
+#[derive(Default)]
+struct Counter {
+    calls: AtomicU64,
+    nanos: AtomicU64,
+}
+
+impl WasiMetrics {
+    fn new(version: &WasiVersion) -> Self {
+        let functions = abi::functions(version);
+        Self {
+            functions,
+            counters: functions.iter().map(|_| Counter::default()).collect(),
+        }
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.functions
+            .binary_search_by(|function| function.name.cmp(name))
+            .ok()
+    }
+
+    fn counter(&self, name: &str) -> Option<&Counter> {
+        self.index(name).map(|index| &self.counters[index])
+    }
+
+    /// Returns how many times the function was called, 0 for functions the version doesn't
+    /// define.
+    pub fn call_count(&self, name: &str) -> u64 {
+        self.counter(name)
+            .map_or(0, |counter| counter.calls.load(Ordering::Relaxed))
+    }
+
+    /// Returns the time spent in the host implementation of the function across all calls.
+    ///
+    /// Calls to `proc_exit` take no time, it exits by trapping.
+    pub fn total_duration(&self, name: &str) -> Duration {
+        self.counter(name).map_or(Duration::ZERO, |counter| {
+            Duration::from_nanos(counter.nanos.load(Ordering::Relaxed))
+        })
+    }
+
+    /// Iterates over the functions that were called, sorted by name, with their call count and
+    /// total duration.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, u64, Duration)> + '_ {
+        self.functions
+            .iter()
+            .zip(self.counters.iter())
+            .filter_map(|(function, counter)| {
+                let calls = counter.calls.load(Ordering::Relaxed);
+                let nanos = counter.nanos.load(Ordering::Relaxed);
+                (calls > 0).then(|| (function.name, calls, Duration::from_nanos(nanos)))
+            })
+    }
+
+    /// Returns a function recording a call to the named function.
+    ///
+    /// The counter is looked up once, recording a call only updates it.
+    fn recorder(&self, name: &str) -> impl Fn(Option<Instant>) + 'static {
+        let counters = self.counters.clone();
+        let index = self.index(name).expect("unknown WASI function");
+        move |start| {
+            let counter = &counters[index];
+            counter.calls.fetch_add(1, Ordering::Relaxed);
+            if let Some(start) = start {
+                let nanos = start.elapsed().as_nanos() as u64;
+                counter.nanos.fetch_add(nanos, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Prints the functions that were called with their call count and total duration.
+impl fmt::Debug for WasiMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.iter()
+                    .map(|(name, calls, duration)| (name, (calls, duration))),
+            )
+            .finish()
+    }
+}
+
+/// Returns the recorders of the functions of an instance, updating the counters of `metrics`.
+fn recorder(metrics: &WasiMetrics) -> impl FnMut(&'static str) -> Option<host::Recorder> + '_ {
+    move |name| Some(Box::new(metrics.recorder(name)))
+}
+
+/// Creates a `wasi_unstable` instance counting the calls to its functions.
+#[cfg(feature = "snapshot0")]
+pub(crate) fn snapshot0(
+    store: &Store,
+    ctx: WasiCtx,
+) -> (wasmtime_wasi::old::snapshot_0::Wasi, WasiMetrics) {
+    let metrics = WasiMetrics::new(&WasiVersion::Snapshot0);
+    let ctx = Rc::new(RefCell::new(ctx));
+    let wasi = host::snapshot0(store, &ctx, false, recorder(&metrics));
+    (wasi, metrics)
+}
+
+/// Creates a `wasi_snapshot_preview1` instance counting the calls to its functions.
+#[cfg(feature = "snapshot1")]
+pub(crate) fn snapshot1(store: &Store, ctx: WasiCtx) -> (wasmtime_wasi::Wasi, WasiMetrics) {
+    let metrics = WasiMetrics::new(&WasiVersion::Snapshot1);
+    let ctx = Rc::new(RefCell::new(ctx));
+    let wasi = host::snapshot1(store, &ctx, false, recorder(&metrics));
+    (wasi, metrics)
+}
@@ -0,0 +1,54 @@
+use crate::{host, AutoWasi, WasiDetectError, WasiVersion};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasi_common::WasiCtx;
+use wasmtime::{Func, Linker, Store};
+
+/// The WASI imports of both snapshots, for linking modules importing from `wasi_unstable` and
+/// `wasi_snapshot_preview1` at once, which [`WasiVersion::detect`](crate::WasiVersion::detect)
+/// rejects with [`WasiDetectError::MixedVersions`](crate::WasiDetectError::MixedVersions).
+///
+/// Both snapshots are backed by the same [`WasiCtx`](wasi_common::WasiCtx), so they share its
+/// args, envs, stdio, preopened directories and fd table. A file opened through one snapshot's
+/// functions can be read through the other's with the same fd.
+#[derive(Debug)]
+pub struct MixedWasi {
+    snapshot0: AutoWasi,
+    snapshot1: AutoWasi,
+}
+
+impl MixedWasi {
+    /// Creates the WASI imports of both snapshots, backed by the same context.
+    pub fn new(store: &Store, ctx: WasiCtx) -> Self {
+        let ctx = Rc::new(RefCell::new(ctx));
+        Self {
+            snapshot0: AutoWasi::Snapshot0(host::snapshot0(store, &ctx, false, |_| None)),
+            snapshot1: AutoWasi::Snapshot1(host::snapshot1(store, &ctx, false, |_| None)),
+        }
+    }
+
+    /// Returns the imports of the old `wasi_unstable` module.
+    pub fn snapshot0(&self) -> &AutoWasi {
+        &self.snapshot0
+    }
+
+    /// Returns the imports of the `wasi_snapshot_preview1` module.
+    pub fn snapshot1(&self) -> &AutoWasi {
+        &self.snapshot1
+    }
+
+    /// Looks up the function called `name` of the provided WASI module, returning it if found.
+    pub fn get_export(&self, module: &str, name: &str) -> Option<&Func> {
+        match WasiVersion::from_module_name(module)? {
+            WasiVersion::Snapshot0 => self.snapshot0.get_export(name),
+            WasiVersion::Snapshot1 => self.snapshot1.get_export(name),
+            _ => None,
+        }
+    }
+
+    /// Adds the items of both snapshots to the specified Linker, each under its own module name.
+    pub fn add_to_linker(&self, linker: &mut Linker) -> Result<(), WasiDetectError> {
+        self.snapshot0.add_to_linker(linker)?;
+        self.snapshot1.add_to_linker(linker)
+    }
+}
@@ -0,0 +1,97 @@
+//! Detection without heap allocations, for hosts that can't afford them.
+
+use crate::{component, exports, DetectLimits, WasiVersion, WasiVersionSet};
+use wasmparser::{Parser, Payload, SectionReader};
+
+/// Returns the only version imported from, applying the same rules as `WasiImports::version`.
+fn single(mut versions: WasiVersionSet) -> Option<WasiVersion> {
+    // WASIX binaries also import the `wasi_snapshot_preview1` functions WASIX extends.
+    if versions.contains(WasiVersion::Wasix32v1) || versions.contains(WasiVersion::Wasix64v1) {
+        versions.remove(WasiVersion::Snapshot1);
+    }
+
+    let mut imported = versions.iter();
+    match (imported.next(), imported.next()) {
+        (None, _) => Some(WasiVersion::default()),
+        (Some(version), None) => Some(version),
+        (Some(_), Some(_)) => None,
+    }
+}
+
+pub(crate) fn detect(binary: &[u8]) -> Option<WasiVersion> {
+    let limits = DetectLimits::default();
+    if !component::is_component(binary) {
+        let (versions, unknown) = versions(binary, &limits)?;
+        if versions.is_empty() && unknown {
+            return None;
+        }
+        if versions.is_empty() {
+            return Some(exports::guess(binary, limits.max_bytes).unwrap_or_default());
+        }
+        return single(versions);
+    }
+
+    if binary.len() > limits.max_bytes {
+        return None;
+    }
+    let mut wasi = false;
+    let mut valid = true;
+    component::for_each_core_module(binary, &mut |module| match versions(module, &limits) {
+        Some((versions, _)) => wasi |= !versions.is_empty(),
+        None => valid = false,
+    })
+    .ok()?;
+
+    if valid && wasi {
+        Some(WasiVersion::Preview2)
+    } else {
+        None
+    }
+}
+
+/// Returns the versions imported from, and whether any import is from an unknown module named
+/// like a WASI version.
+///
+/// Returns `None` if the binary exceeds the limits, like `WasiImports::parse_with_limits`.
+fn versions(binary: &[u8], limits: &DetectLimits) -> Option<(WasiVersionSet, bool)> {
+    let mut versions = WasiVersionSet::new();
+    let mut unknown = false;
+
+    for payload in Parser::new(0).parse_all(binary) {
+        let end = match payload.ok()? {
+            Payload::Version { .. } => continue,
+            Payload::CustomSection {
+                data_offset, data, ..
+            } => data_offset + data.len(),
+            Payload::TypeSection(reader) => reader.range().end,
+            Payload::ImportSection(reader) => {
+                if reader.range().end > limits.max_bytes
+                    || reader.get_count() as usize > limits.max_imports
+                {
+                    return None;
+                }
+                for import in reader {
+                    let import = import.ok()?;
+                    let name = import.field.unwrap_or_default();
+                    if import.module.len().max(name.len()) > limits.max_name_len {
+                        return None;
+                    }
+                    match WasiVersion::from_module_name(import.module) {
+                        Some(version) => {
+                            versions.insert(version);
+                        }
+                        None => unknown |= WasiVersion::is_unknown_module(import.module, name),
+                    }
+                }
+                break;
+            }
+            // Imports must come before every other section, so there are none left to find.
+            _ => break,
+        };
+        if end > limits.max_bytes {
+            return None;
+        }
+    }
+
+    Some((versions, unknown))
+}
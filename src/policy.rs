@@ -0,0 +1,119 @@
+use crate::{abi, Capability, WasiVersion};
+use wasmtime::{Func, Store, Trap, Val};
+
+/// The `nosys` errno, which is the same in both snapshots.
+const NOSYS: i32 = 52;
+/// The `notcapable` errno, which is the same in both snapshots.
+const NOTCAPABLE: i32 = 76;
+
+/// The WASI functions an [`AutoWasi`](crate::AutoWasi) created with
+/// [`AutoWasi::with_policy`](crate::AutoWasi::with_policy) denies guests.
+///
+/// Functions are denied by the [`Capability`](crate::Capability) they need, the same grouping
+/// [`WasiVersion::required_capabilities`](crate::WasiVersion::required_capabilities) reports,
+/// so a policy denying the capabilities a binary requires denies every function it imports
+/// that needs them. `proc_exit` needs no capability and is denied separately with
+/// [`deny_exit`](Policy::deny_exit).
+///
+/// The [default](Policy::default) policy allows everything.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// let mut policy = Policy::new();
+/// policy
+///     .deny(Capability::Filesystem)
+///     .deny(Capability::Clocks)
+///     .deny(Capability::Environment)
+///     .denial(Denial::NoSys);
+/// assert!(!policy.allows("path_open"));
+/// assert!(policy.allows("fd_write"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Policy {
+    denied: Vec<Capability>,
+    deny_exit: bool,
+    denial: Denial,
+}
+
+impl Policy {
+    /// Creates a policy allowing everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Denies the functions needing the capability.
+    pub fn deny(&mut self, capability: Capability) -> &mut Self {
+        if !self.denied.contains(&capability) {
+            self.denied.push(capability);
+        }
+        self
+    }
+
+    /// Denies `proc_exit`, which always traps when denied as guests don't expect it to return.
+    pub fn deny_exit(&mut self) -> &mut Self {
+        self.deny_exit = true;
+        self
+    }
+
+    /// Sets what denied functions do when called, returning
+    /// [`Denial::NotCapable`](crate::Denial::NotCapable) by default.
+    pub fn denial(&mut self, denial: Denial) -> &mut Self {
+        self.denial = denial;
+        self
+    }
+
+    /// Returns whether the policy allows the WASI function.
+    pub fn allows(&self, name: &str) -> bool {
+        match Capability::from_function(name) {
+            Some(capability) => !self.denied.contains(&capability),
+            None => !(self.deny_exit && name == "proc_exit"),
+        }
+    }
+
+    /// Returns the functions replacing the ones of the version the policy denies.
+    pub(crate) fn denied_functions(
+        &self,
+        store: &Store,
+        version: &WasiVersion,
+    ) -> Vec<(&'static str, Func)> {
+        abi::functions(version)
+            .iter()
+            .filter(|function| !self.allows(function.name))
+            .map(|function| (function.name, self.denied_function(store, function)))
+            .collect()
+    }
+
+    fn denied_function(&self, store: &Store, function: &abi::Function) -> Func {
+        let name = function.name;
+        let errno = match self.denial {
+            // Guests don't check for `proc_exit` returning.
+            _ if name == "proc_exit" => None,
+            Denial::NotCapable => Some(NOTCAPABLE),
+            Denial::NoSys => Some(NOSYS),
+            Denial::Trap => None,
+        };
+        Func::new(store, function.ty(), move |_, _, results| match errno {
+            Some(errno) => {
+                results[0] = Val::I32(errno);
+                Ok(())
+            }
+            None => Err(Trap::new(format!(
+                "WASI function `{}` is denied by the policy",
+                name
+            ))),
+        })
+    }
+}
+
+/// What a function denied by a [`Policy`](crate::Policy) does when called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Denial {
+    /// Return the `notcapable` errno, as if the guest lacked the rights, the default.
+    #[default]
+    NotCapable,
+    /// Return the `nosys` errno, as if the host didn't implement the function.
+    NoSys,
+    /// Trap, stopping the guest.
+    Trap,
+}
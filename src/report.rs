@@ -0,0 +1,209 @@
+use crate::{WasiExtension, WasiVersion};
+use std::fmt;
+use wasmtime::FuncType;
+
+/// A detailed description of the WASI imports of a binary.
+///
+/// Created by [`WasiVersion::inspect`](crate::WasiVersion::inspect).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DetectionReport {
+    /// The detected WASI version, or `None` if the binary doesn't import any WASI version and
+    /// its exports don't hint at one.
+    pub version: Option<WasiVersion>,
+    /// Whether the version was detected from the imports or guessed from the exports, or `None`
+    /// without a version.
+    pub source: Option<VersionSource>,
+    /// Every WASI import of the binary, in the order they appear.
+    pub imports: Vec<WasiImport>,
+    /// The names of the imported functions the detected version doesn't define, such as
+    /// functions of a fork, in the order they first appear.
+    ///
+    /// Only imports from `wasi_unstable` and `wasi_snapshot_preview1` are checked.
+    pub unknown_functions: Vec<String>,
+    /// The imports from experimental WASI extensions, by module in the order they first appear.
+    pub extensions: Vec<ExtensionImports>,
+    /// The imports from modules that aren't a WASI version, by module in the order they first
+    /// appear.
+    pub unresolved: Vec<UnresolvedImports>,
+}
+
+/// What a [`DetectionReport`] detected the version from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum VersionSource {
+    /// The modules the binary imports from.
+    Imports,
+    /// The names the binary exports, for binaries without WASI imports. See
+    /// [`DetectionConfig::export_heuristics`](crate::DetectionConfig::export_heuristics) for the
+    /// names recognized.
+    Exports,
+}
+
+/// An import of a binary from a WASI module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WasiImport {
+    /// The name of the module imported from, such as `wasi_snapshot_preview1`.
+    pub module: String,
+    /// The name of the imported field, such as `fd_write`.
+    pub name: String,
+    /// The index of the function's type in the type section, or `None` if the import isn't a
+    /// function.
+    pub type_index: Option<u32>,
+}
+
+/// The imports of a binary from the module of an experimental WASI extension.
+///
+/// Created by [`WasiVersion::detect_extensions`](crate::WasiVersion::detect_extensions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtensionImports {
+    /// The extension the module belongs to.
+    pub extension: WasiExtension,
+    /// The name of the module imported from, such as `wasi_ephemeral_nn`.
+    pub module: String,
+    /// The names of the imported fields, in the order they appear.
+    pub names: Vec<String>,
+}
+
+/// The imports of a binary from a module that isn't a WASI version, such as `env`.
+///
+/// An [`AutoWasi`](crate::AutoWasi) doesn't provide any of these, they have to be linked by the
+/// host. Created by [`AutoWasi::unresolved_imports`](crate::AutoWasi::unresolved_imports).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnresolvedImports {
+    /// The name of the module imported from.
+    pub module: String,
+    /// The imports from the module, in the order they appear.
+    pub imports: Vec<UnresolvedImport>,
+}
+
+/// An import of a binary from a module that isn't a WASI version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnresolvedImport {
+    /// The name of the imported field.
+    pub name: String,
+    /// The kind of the imported item.
+    pub kind: ImportKind,
+}
+
+/// The kind of an imported item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum ImportKind {
+    /// A function.
+    Func,
+    /// A table.
+    Table,
+    /// A memory.
+    Memory,
+    /// A global.
+    Global,
+    /// Anything else, such as the modules and instances of the module linking proposal.
+    Other,
+}
+
+/// Formats the kind as in the text format, such as `func`.
+impl fmt::Display for ImportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Func => write!(f, "func"),
+            Self::Table => write!(f, "table"),
+            Self::Memory => write!(f, "memory"),
+            Self::Global => write!(f, "global"),
+            Self::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// A WASI function imported with a different signature than the one WASI defines.
+///
+/// Created by [`WasiVersion::validate`](crate::WasiVersion::validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureMismatch {
+    /// The name of the imported function, such as `fd_write`.
+    pub name: String,
+    /// The signature WASI defines for the function.
+    pub expected: FuncType,
+    /// The signature the function is imported with, or `None` if the import isn't a function.
+    pub actual: Option<FuncType>,
+}
+
+/// A WASI import of a module that an [`AutoWasi`](crate::AutoWasi) doesn't provide, or provides
+/// with a different type.
+///
+/// Created by [`AutoWasi::missing_exports`](crate::AutoWasi::missing_exports).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingExport {
+    /// The name of the imported field, such as `fd_write`.
+    pub name: String,
+    /// The kind of the imported item.
+    pub kind: ImportKind,
+    /// The type the function is imported with, or `None` if the import isn't a function.
+    pub ty: Option<FuncType>,
+    /// The type of the function provided under the same name, or `None` if there isn't one.
+    pub provided: Option<FuncType>,
+}
+
+/// Formats the import as in the text format, such as `fd_write (func (param i32) (result i32))`.
+impl fmt::Display for MissingExport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ty = match &self.ty {
+            Some(ty) => ty,
+            None => return write!(f, "{} ({})", self.name, self.kind),
+        };
+        write!(f, "{} {}", self.name, FuncTypeText(ty))
+    }
+}
+
+/// The WASI imports of a binary an [`AutoWasi`](crate::AutoWasi) provides and doesn't provide.
+///
+/// Created by [`AutoWasi::inspect_imports`](crate::AutoWasi::inspect_imports).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportReport {
+    /// The names of the imports provided with the type they're imported with, in the order they
+    /// appear.
+    pub satisfied: Vec<String>,
+    /// The names of the imports that aren't provided, or are provided with a different type or
+    /// kind, in the order they appear.
+    pub unsatisfied: Vec<String>,
+}
+
+impl ImportReport {
+    /// Returns whether every import is provided, so linking the instance resolves all of them.
+    pub fn is_satisfied(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+}
+
+/// Formats a function type as in the text format, such as `(func (param i32) (result i32))`.
+pub(crate) struct FuncTypeText<'a>(pub(crate) &'a FuncType);
+
+impl fmt::Display for FuncTypeText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(func")?;
+        if self.0.params().len() > 0 {
+            write!(f, " (param")?;
+            for param in self.0.params() {
+                write!(f, " {}", param)?;
+            }
+            write!(f, ")")?;
+        }
+        if self.0.results().len() > 0 {
+            write!(f, " (result")?;
+            for result in self.0.results() {
+                write!(f, " {}", result)?;
+            }
+            write!(f, ")")?;
+        }
+        write!(f, ")")
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{RequirementError, WasiDetectError, WasiVersion};
+use std::fmt;
+
+/// A constraint on the WASI version of binaries, such as a host requiring at least
+/// [`WasiVersion::Snapshot1`].
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// let requirement = WasiVersionRequirement::at_least(WasiVersion::Snapshot1);
+/// assert!(requirement.satisfied_by(WasiVersion::Preview2));
+/// assert!(!requirement.satisfied_by(WasiVersion::Snapshot0));
+/// assert_eq!(requirement.to_string(), ">=snapshot1");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WasiVersionRequirement {
+    version: WasiVersion,
+    exact: bool,
+}
+
+impl WasiVersionRequirement {
+    /// Requires the version or a newer one, following the
+    /// [release order](WasiVersion#impl-Ord-for-WasiVersion).
+    pub fn at_least(version: WasiVersion) -> Self {
+        Self {
+            version,
+            exact: false,
+        }
+    }
+
+    /// Requires exactly the version.
+    pub fn exact(version: WasiVersion) -> Self {
+        Self {
+            version,
+            exact: true,
+        }
+    }
+
+    /// Returns whether the version satisfies this requirement.
+    pub fn satisfied_by(&self, version: WasiVersion) -> bool {
+        if self.exact {
+            version == self.version
+        } else {
+            version.is_at_least(self.version)
+        }
+    }
+
+    /// Checks that the version detected from the binary satisfies this requirement.
+    ///
+    /// Fails with
+    /// [`WasiDetectError::UnsatisfiedRequirement`](crate::WasiDetectError::UnsatisfiedRequirement)
+    /// if it doesn't. Like [`AutoWasi::new_checked`](crate::AutoWasi::new_checked), binaries
+    /// without WASI imports satisfy any requirement.
+    pub fn check_binary<T: AsRef<[u8]>>(&self, binary: T) -> Result<(), WasiDetectError> {
+        match WasiVersion::try_detect(binary)? {
+            Some(found) if !self.satisfied_by(found) => Err(RequirementError {
+                requirement: *self,
+                found,
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Formats the requirement like a semver requirement, such as `>=snapshot1` or `=preview2`.
+impl fmt::Display for WasiVersionRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exact {
+            write!(f, "={}", self.version)
+        } else {
+            write!(f, ">={}", self.version)
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Rewriting binaries to import another WASI version.
+
+use crate::{abi, func_type, text_to_binary, WasiDetectError, WasiVersion};
+use std::collections::HashMap;
+use wasm_encoder::{CodeSection, Encode, Function, Instruction, Module, RawSection};
+use wasmparser::{
+    BinaryReader, ElementItem, ExternalKind, FunctionBody, ImportSectionEntryType, Operator,
+    Parser, Payload, TypeDef,
+};
+
+const IMPORT_SECTION: u8 = 2;
+const FUNCTION_SECTION: u8 = 3;
+const CODE_SECTION: u8 = 10;
+
+/// The `wasi_unstable` functions writing structures to memory that grew in
+/// `wasi_snapshot_preview1`, which an adapter can't translate without memory of its own.
+const UNADAPTABLE: &[&str] = &["fd_filestat_get", "path_filestat_get", "poll_oneoff"];
+
+/// Rewrites the module's `wasi_unstable` imports to import from `wasi_snapshot_preview1`
+/// instead.
+///
+/// Most functions have the same signatures and layouts in both snapshots and are only renamed.
+/// Calls to `fd_seek`, whose `whence` values were reordered, go through an adapter function
+/// appended to the module that converts them. Fails with
+/// [`WasiDetectError::NotUpgradable`](crate::WasiDetectError::NotUpgradable) listing the
+/// imports that can't be upgraded this way, such as functions whose structures grew or an
+/// `fd_seek` that's exported or referenced by a table.
+///
+/// Binaries that don't import `wasi_unstable` are returned unchanged. Components aren't
+/// supported and fail to parse.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// # fn test() -> anyhow::Result<()> {
+/// let wasm = wat::parse_str(r#"(module (import "wasi_unstable" "sched_yield" (func (result i32))))"#)?;
+/// let upgraded = rewrite::upgrade_to_preview1(&wasm)?;
+/// assert_eq!(WasiVersion::detect(upgraded)?, WasiVersion::Snapshot1);
+/// # Ok(()) }
+/// ```
+pub fn upgrade_to_preview1(binary: &[u8]) -> Result<Vec<u8>, WasiDetectError> {
+    let binary = text_to_binary(binary)?;
+    let scan = Scan::parse(&binary)?;
+    if !scan.unsupported.is_empty() {
+        return Err(WasiDetectError::NotUpgradable {
+            functions: scan.unsupported,
+        });
+    }
+    if !scan.upgrades {
+        return Ok(binary.into_owned());
+    }
+
+    let mut module = Module::new();
+    let mut reader = BinaryReader::new(&binary);
+    // The magic and version are emitted by the module.
+    reader.read_bytes(8)?;
+    while !reader.eof() {
+        let id = reader.read_u8()? as u8;
+        let size = reader.read_var_u32()? as usize;
+        let start = reader.original_position();
+        let data = reader.read_bytes(size)?;
+        match id {
+            IMPORT_SECTION => module.section(&RawSection {
+                id,
+                data: &scan.import_section(&binary),
+            }),
+            FUNCTION_SECTION if !scan.adapters.is_empty() => module.section(&RawSection {
+                id,
+                data: &scan.function_section(data, start)?,
+            }),
+            CODE_SECTION if !scan.adapters.is_empty() => {
+                module.section(&scan.code_section(&binary)?)
+            }
+            _ => module.section(&RawSection { id, data }),
+        };
+    }
+
+    Ok(module.finish())
+}
+
+/// What rewriting a module has to change.
+#[derive(Default)]
+struct Scan {
+    /// Whether the module imports from `wasi_unstable`.
+    upgrades: bool,
+    /// The range of each import, and the offset its module name ends at for `wasi_unstable`
+    /// imports.
+    imports: Vec<(usize, usize, Option<usize>)>,
+    imported_funcs: u32,
+    defined_funcs: u32,
+    /// The `fd_seek` imports calls are redirected to an adapter for.
+    adapters: Vec<Adapter>,
+    bodies: Vec<(usize, usize)>,
+    unsupported: Vec<String>,
+}
+
+impl Scan {
+    fn parse(binary: &[u8]) -> Result<Self, WasiDetectError> {
+        let mut scan = Self::default();
+        let mut types = Vec::new();
+
+        for payload in Parser::new(0).parse_all(binary) {
+            match payload? {
+                Payload::TypeSection(reader) => {
+                    for ty in reader {
+                        types.push(match ty? {
+                            TypeDef::Func(ty) => func_type(&ty),
+                            _ => None,
+                        });
+                    }
+                }
+                Payload::ImportSection(mut reader) => {
+                    for _ in 0..reader.get_count() {
+                        let start = reader.original_position();
+                        let import = reader.read()?;
+                        let end = reader.original_position();
+                        scan.add_import(binary, start, end, import, &types)?;
+                    }
+                }
+                Payload::FunctionSection(reader) => scan.defined_funcs = reader.get_count(),
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export?;
+                        if let ExternalKind::Function = export.kind {
+                            scan.reference(export.index);
+                        }
+                    }
+                }
+                Payload::StartSection { func, .. } => scan.reference(func),
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        for item in element?.items.get_items_reader()? {
+                            if let ElementItem::Func(index) = item? {
+                                scan.reference(index);
+                            }
+                        }
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        for operator in global?.init_expr.get_operators_reader() {
+                            if let Operator::RefFunc { function_index } = operator? {
+                                scan.reference(function_index);
+                            }
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => scan.add_body(&body)?,
+                _ => {}
+            }
+        }
+
+        // Imports that are never called don't need an adapter.
+        scan.adapters.retain(|adapter| adapter.called);
+        Ok(scan)
+    }
+
+    fn add_import(
+        &mut self,
+        binary: &[u8],
+        start: usize,
+        end: usize,
+        import: wasmparser::Import,
+        types: &[Option<wasmtime::FuncType>],
+    ) -> Result<(), WasiDetectError> {
+        let type_index = match import.ty {
+            ImportSectionEntryType::Function(index) => Some(index),
+            _ => None,
+        };
+        let index = self.imported_funcs;
+        if type_index.is_some() {
+            self.imported_funcs += 1;
+        }
+
+        if WasiVersion::from_module_name(import.module) != Some(WasiVersion::Snapshot0) {
+            self.imports.push((start, end, None));
+            return Ok(());
+        }
+        let mut reader = BinaryReader::new_with_offset(&binary[start..end], start);
+        reader.read_string()?;
+        self.imports
+            .push((start, end, Some(reader.original_position())));
+        self.upgrades = true;
+
+        let name = import.field.unwrap_or_default();
+        if UNADAPTABLE.contains(&name) {
+            self.unsupport(name);
+        } else if let ("fd_seek", Some(type_index)) = (name, type_index) {
+            let expected = abi::function(&WasiVersion::Snapshot0, name).map(abi::Function::ty);
+            match types.get(type_index as usize) {
+                Some(Some(ty)) if Some(ty) == expected.as_ref() => self.adapters.push(Adapter {
+                    import: index,
+                    type_index,
+                    called: false,
+                }),
+                _ => self.unsupport(name),
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a use of the function other than a direct call, which adapters can't redirect.
+    fn reference(&mut self, index: u32) {
+        if self.adapters.iter().any(|adapter| adapter.import == index) {
+            self.unsupport("fd_seek");
+        }
+    }
+
+    fn unsupport(&mut self, name: &str) {
+        if !self
+            .unsupported
+            .iter()
+            .any(|unsupported| unsupported == name)
+        {
+            self.unsupported.push(name.to_string());
+        }
+    }
+
+    fn add_body(&mut self, body: &FunctionBody) -> Result<(), WasiDetectError> {
+        let range = body.range();
+        self.bodies.push((range.start, range.end));
+        for operator in body.get_operators_reader()? {
+            match operator? {
+                Operator::Call { function_index } => {
+                    let adapter = self
+                        .adapters
+                        .iter_mut()
+                        .find(|adapter| adapter.import == function_index);
+                    if let Some(adapter) = adapter {
+                        adapter.called = true;
+                    }
+                }
+                Operator::RefFunc { function_index } => self.reference(function_index),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn import_section(&self, binary: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        (self.imports.len() as u32).encode(&mut data);
+        for (start, end, module_end) in &self.imports {
+            match module_end {
+                Some(module_end) => {
+                    WasiVersion::Snapshot1.module_name().encode(&mut data);
+                    data.extend_from_slice(&binary[*module_end..*end]);
+                }
+                None => data.extend_from_slice(&binary[*start..*end]),
+            }
+        }
+        data
+    }
+
+    /// Appends the type of each adapter to the function section.
+    fn function_section(&self, data: &[u8], offset: usize) -> Result<Vec<u8>, WasiDetectError> {
+        let mut reader = BinaryReader::new_with_offset(data, offset);
+        let count = reader.read_var_u32()?;
+        let entries = &data[reader.current_position()..];
+
+        let mut section = Vec::new();
+        (count + self.adapters.len() as u32).encode(&mut section);
+        section.extend_from_slice(entries);
+        for adapter in &self.adapters {
+            adapter.type_index.encode(&mut section);
+        }
+        Ok(section)
+    }
+
+    /// Redirects the calls to each `fd_seek` import to its adapter, then appends the adapters.
+    fn code_section(&self, binary: &[u8]) -> Result<CodeSection, WasiDetectError> {
+        let first = self.imported_funcs + self.defined_funcs;
+        let adapters: HashMap<_, _> = self
+            .adapters
+            .iter()
+            .zip(first..)
+            .map(|(adapter, index)| (adapter.import, index))
+            .collect();
+
+        let mut code = CodeSection::new();
+        for (start, end) in &self.bodies {
+            let body = FunctionBody::new(*start, &binary[*start..*end]);
+            let mut rewritten = Vec::new();
+            let mut copied = *start;
+            let mut operators = body.get_operators_reader()?;
+            while !operators.eof() {
+                let (operator, offset) = operators.read_with_offset()?;
+                if let Operator::Call { function_index } = operator {
+                    if let Some(adapter) = adapters.get(&function_index) {
+                        // Keep the opcode, replacing the function index following it.
+                        rewritten.extend_from_slice(&binary[copied..=offset]);
+                        adapter.encode(&mut rewritten);
+                        copied = operators.original_position();
+                    }
+                }
+            }
+            rewritten.extend_from_slice(&binary[copied..*end]);
+            code.raw(&rewritten);
+        }
+
+        for adapter in &self.adapters {
+            code.function(&whence_adapter(adapter.import));
+        }
+        Ok(code)
+    }
+}
+
+struct Adapter {
+    /// The function index of the import.
+    import: u32,
+    /// The type index of the import, which the adapter shares.
+    type_index: u32,
+    /// Whether the module calls the import.
+    called: bool,
+}
+
+/// Returns a function calling the `fd_seek` import with the `wasi_unstable` `whence` converted.
+///
+/// `wasi_unstable` orders `whence` as `cur`, `end`, `set` while `wasi_snapshot_preview1` orders
+/// it as `set`, `cur`, `end`. Invalid values are passed as is for the import to reject.
+fn whence_adapter(import: u32) -> Function {
+    let mut function = Function::new(std::iter::empty());
+    for instruction in &[
+        Instruction::LocalGet(0),
+        Instruction::LocalGet(1),
+        Instruction::LocalGet(2),
+        // (whence + 1) % 3
+        Instruction::LocalGet(2),
+        Instruction::I32Const(1),
+        Instruction::I32Add,
+        Instruction::I32Const(3),
+        Instruction::I32RemU,
+        // whence > 2
+        Instruction::LocalGet(2),
+        Instruction::I32Const(2),
+        Instruction::I32GtU,
+        Instruction::Select,
+        Instruction::LocalGet(3),
+        Instruction::Call(import),
+        Instruction::End,
+    ] {
+        function.instruction(instruction);
+    }
+    function
+}
@@ -0,0 +1,158 @@
+use crate::WasiVersion;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// A set of WASI versions, such as the versions a binary imports from or the versions a host can
+/// serve.
+///
+/// Returned by [`WasiVersion::detect_all`](crate::WasiVersion::detect_all). Iterating yields the
+/// versions from oldest to newest.
+///
+/// ```rust
+/// # use auto_wasi::*;
+/// let host: WasiVersionSet = [WasiVersion::Snapshot0, WasiVersion::Snapshot1]
+///     .iter()
+///     .copied()
+///     .collect();
+/// let mut binary = WasiVersionSet::none();
+/// binary.insert(WasiVersion::Snapshot1);
+/// binary.insert(WasiVersion::Preview2);
+///
+/// let served = host.intersection(&binary);
+/// assert_eq!(served.iter().collect::<Vec<_>>(), [WasiVersion::Snapshot1]);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WasiVersionSet {
+    bits: u32,
+}
+
+impl WasiVersionSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a set of every version this release knows.
+    pub fn all() -> Self {
+        Self {
+            bits: (1 << WasiVersion::VERSIONS.len()) - 1,
+        }
+    }
+
+    /// Creates an empty set, like [`new`](Self::new).
+    pub fn none() -> Self {
+        Self::new()
+    }
+
+    fn bit(version: WasiVersion) -> u32 {
+        1 << version.release()
+    }
+
+    /// Adds the version to the set, returning whether it wasn't in the set before.
+    pub fn insert(&mut self, version: WasiVersion) -> bool {
+        let contained = self.contains(version);
+        self.bits |= Self::bit(version);
+        !contained
+    }
+
+    /// Removes the version from the set, returning whether it was in the set.
+    pub fn remove(&mut self, version: WasiVersion) -> bool {
+        let contained = self.contains(version);
+        self.bits &= !Self::bit(version);
+        contained
+    }
+
+    /// Returns whether the set contains the version.
+    pub fn contains(&self, version: WasiVersion) -> bool {
+        self.bits & Self::bit(version) != 0
+    }
+
+    /// Returns whether the set doesn't contain any version.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns the number of versions in the set.
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    /// Returns the versions in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns the versions in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self {
+            bits: self.bits & other.bits,
+        }
+    }
+
+    /// Returns the versions in the set, from oldest to newest.
+    pub fn iter(&self) -> WasiVersionSetIter {
+        WasiVersionSetIter { bits: self.bits }
+    }
+}
+
+impl fmt::Debug for WasiVersionSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl IntoIterator for WasiVersionSet {
+    type Item = WasiVersion;
+    type IntoIter = WasiVersionSetIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for &WasiVersionSet {
+    type Item = WasiVersion;
+    type IntoIter = WasiVersionSetIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<WasiVersion> for WasiVersionSet {
+    fn from_iter<I: IntoIterator<Item = WasiVersion>>(versions: I) -> Self {
+        let mut set = Self::new();
+        for version in versions {
+            set.insert(version);
+        }
+        set
+    }
+}
+
+/// An iterator over the versions in a [`WasiVersionSet`], from oldest to newest.
+#[derive(Debug, Clone)]
+pub struct WasiVersionSetIter {
+    bits: u32,
+}
+
+impl Iterator for WasiVersionSetIter {
+    type Item = WasiVersion;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bits == 0 {
+            return None;
+        }
+        let index = self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1;
+        Some(WasiVersion::VERSIONS[index])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bits.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for WasiVersionSetIter {}
@@ -0,0 +1,419 @@
+mod common;
+
+use auto_wasi::{AutoWasi, WasiDetectError, WasiVersion};
+use wasi_common::WasiCtx;
+use wasmtime::Store;
+
+fn ctx() -> WasiCtx {
+    WasiCtx::new(["test"]).expect("failed to create context")
+}
+
+#[test]
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+fn as_snapshot() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasi = AutoWasi::new(&store, ctx(), WasiVersion::Snapshot0).expect("failed to create wasi");
+    assert!(wasi.as_snapshot0().is_some());
+    assert!(wasi.as_snapshot1().is_none());
+    assert!(wasi.shallow_clone().into_snapshot1().is_none());
+    assert!(wasi.into_snapshot0().is_some());
+
+    let wasi = AutoWasi::new(&store, ctx(), WasiVersion::Snapshot1).expect("failed to create wasi");
+    assert!(wasi.as_snapshot0().is_none());
+    assert!(wasi.as_snapshot1().is_some());
+    assert!(wasi.shallow_clone().into_snapshot0().is_none());
+    assert!(wasi.into_snapshot1().is_some());
+}
+
+#[test]
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+fn version() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for version in &[WasiVersion::Snapshot0, WasiVersion::Snapshot1] {
+        let wasi = AutoWasi::new(&store, ctx(), *version).expect("failed to create wasi");
+        assert_eq!(&wasi.version(), version);
+        assert_eq!(&WasiVersion::from(&wasi), version);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+fn detect_version() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("snapshot_0");
+    let wasi = AutoWasi::detect(&store, ctx(), binary).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot0);
+
+    let binary = &common::fixture("snapshot_1");
+    let wasi = AutoWasi::detect(&store, ctx(), binary).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot1);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn detect_reactor_without_imports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("initialize_export");
+    let wasi = AutoWasi::detect(&store, ctx(), binary).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot1);
+}
+
+#[test]
+#[cfg(feature = "snapshot0")]
+fn detect_from_path() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasi = AutoWasi::detect_from_path(&store, ctx(), common::fixture_path("snapshot_0"))
+        .expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot0);
+}
+
+#[test]
+#[cfg(all(feature = "wat", feature = "snapshot0"))]
+fn detect_wat() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wat = r#"(module (import "wasi_unstable" "proc_exit" (func (param i32))))"#;
+    let wasi = AutoWasi::detect_wat(&store, ctx(), wat).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot0);
+}
+
+#[test]
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+fn debug() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasi = AutoWasi::new(&store, ctx(), WasiVersion::Snapshot0).expect("failed to create wasi");
+    assert_eq!(format!("{:?}", wasi), "AutoWasi { version: Snapshot0, .. }");
+
+    let ctx = wasi_common::WasiCtxBuilder::new()
+        .args(["test", "--token=hunter2"])
+        .env("SECRET", "hunter2")
+        .build()
+        .expect("failed to create context");
+    let wasi = AutoWasi::new(&store, ctx, WasiVersion::Snapshot1).expect("failed to create wasi");
+    let debug = format!("{:#?}", wasi);
+    assert!(!debug.contains("hunter2"), "{}", debug);
+    assert!(!debug.contains("SECRET"), "{}", debug);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn shallow_clone() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasi = AutoWasi::new(&store, ctx(), WasiVersion::Snapshot1).expect("failed to create wasi");
+    let clone = wasi.shallow_clone();
+    assert_eq!(clone.version(), wasi.version());
+    for name in &["fd_write", "proc_exit", "random_get"] {
+        let func = clone.get_export(name).expect("missing export");
+        assert_eq!(Some(func.ty()), wasi.get_export(name).map(|func| func.ty()));
+    }
+
+    let mut linker = wasmtime::Linker::new(&store);
+    clone
+        .add_to_linker(&mut linker)
+        .expect("failed to add clone to linker");
+}
+
+#[test]
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+fn exports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for version in &[WasiVersion::Snapshot0, WasiVersion::Snapshot1] {
+        let wasi = AutoWasi::new(&store, ctx(), *version).expect("failed to create wasi");
+        let exports: Vec<_> = wasi.exports().collect();
+        assert_eq!(exports.len(), 45);
+        assert_eq!(wasi.get_function_count(), 45);
+
+        let mut linker = wasmtime::Linker::new(&store);
+        wasi.add_to_linker(&mut linker)
+            .expect("failed to add to linker");
+        assert_eq!(linker.iter().count(), wasi.get_function_count());
+        for (name, func) in exports {
+            let export = wasi.get_export(name).expect("missing export");
+            assert!(
+                std::ptr::eq(func, export),
+                "{} isn't the same function",
+                name
+            );
+        }
+        assert!(wasi.exports().any(|(name, _)| name == "fd_write"));
+    }
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn with_inherit_stdio() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("snapshot_1");
+    let wasi = AutoWasi::with_inherit_stdio(&store, binary).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot1);
+
+    let result = AutoWasi::with_inherit_stdio(&store, b"\0asm\x01\0");
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn with_envs() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("snapshot_1");
+    let envs = vec![("RUST_LOG".to_string(), "debug".to_string())];
+    let wasi = AutoWasi::with_envs(&store, envs, binary).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot1);
+
+    let result = AutoWasi::with_envs(&store, [("RUST_LOG", "debug")], b"\0asm\x01\0");
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+}
+
+#[test]
+#[cfg(feature = "snapshot0")]
+fn with_args_and_envs() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("snapshot_0");
+    let wasi = AutoWasi::with_args_and_envs(
+        &store,
+        &["app", "--verbose"],
+        &[("RUST_LOG", "debug")],
+        binary,
+    )
+    .expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot0);
+
+    let result = AutoWasi::with_args_and_envs(&store, &[], &[], b"\0asm\x01\0");
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+}
+
+#[test]
+fn new_unsupported_version() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for version in &[
+        WasiVersion::Preview2,
+        WasiVersion::Wasix32v1,
+        WasiVersion::Wasix64v1,
+    ] {
+        match AutoWasi::new(&store, ctx(), *version) {
+            Err(WasiDetectError::UnsupportedVersion(unsupported)) => {
+                assert_eq!(&unsupported, version)
+            }
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("created wasi for an unsupported version"),
+        }
+    }
+}
+
+#[test]
+#[cfg(not(all(feature = "snapshot0", feature = "snapshot1")))]
+fn new_disabled_version() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let version = if cfg!(feature = "snapshot0") {
+        WasiVersion::Snapshot1
+    } else {
+        WasiVersion::Snapshot0
+    };
+    match AutoWasi::new(&store, ctx(), version) {
+        Err(WasiDetectError::UnsupportedVersion(unsupported)) => assert_eq!(unsupported, version),
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("created wasi for a disabled version"),
+    }
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn instantiate() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+            (memory (export "memory") 1)
+        )"#,
+    )
+    .unwrap();
+    let (instance, version) =
+        AutoWasi::instantiate(&store, ctx(), &binary).expect("failed to instantiate");
+    assert_eq!(version, WasiVersion::Snapshot1);
+    assert!(instance.get_memory("memory").is_some());
+
+    let result = AutoWasi::instantiate(&store, ctx(), b"\0asm\x01\0");
+    assert!(matches!(result, Err(WasiDetectError::Compile(_))));
+
+    let binary = &common::fixture("env");
+    let result = AutoWasi::instantiate(&store, ctx(), binary);
+    assert!(matches!(result, Err(WasiDetectError::Instantiate(_))));
+}
+
+#[test]
+#[cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+fn new_checked() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("snapshot_1");
+    let wasi = AutoWasi::new_checked(&store, ctx(), WasiVersion::Snapshot1, binary)
+        .expect("failed to create wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot1);
+
+    let error = match AutoWasi::new_checked(&store, ctx(), WasiVersion::Snapshot0, binary) {
+        Err(WasiDetectError::VersionMismatch(error)) => error,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(
+        error,
+        auto_wasi::WasiVersionMismatchError {
+            expected: WasiVersion::Snapshot0,
+            found: WasiVersion::Snapshot1,
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "expected a binary importing wasi_unstable but it imports wasi_snapshot_preview1"
+    );
+
+    let binary = &common::fixture("env");
+    let wasi = AutoWasi::new_checked(&store, ctx(), WasiVersion::Snapshot0, binary)
+        .expect("failed to create wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot0);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn with_clock_override() {
+    if common::engine_unavailable() {
+        return;
+    }
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use wasmtime::{Linker, Module};
+
+    let store = Store::default();
+    let wasm = common::fixture("clock");
+    let ticks = Rc::new(Cell::new(0));
+    let clock = ticks.clone();
+    let wasi = AutoWasi::with_clock_override(&store, ctx(), &wasm, move || {
+        clock.set(clock.get() + 1);
+        clock.get() * 1_000
+    })
+    .expect("failed to create wasi");
+
+    let mut linker = Linker::new(&store);
+    wasi.add_to_linker(&mut linker)
+        .expect("failed to add to linker");
+    let module = Module::new(store.engine(), &wasm).expect("invalid fixture");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+    let time = instance
+        .get_func("time")
+        .unwrap()
+        .get1::<i32, i64>()
+        .unwrap();
+
+    // Monotonic, then realtime.
+    assert_eq!(time(1).unwrap(), 1_000);
+    assert_eq!(time(0).unwrap(), 2_000);
+    // Unknown clocks fail without reading the clock.
+    assert_eq!(time(4).unwrap(), -1);
+    assert_eq!(ticks.get(), 2);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn with_overrides() {
+    if common::engine_unavailable() {
+        return;
+    }
+    use wasmtime::{Caller, Extern, Func, Linker, Module};
+
+    let store = Store::default();
+    let random_get = Func::wrap(&store, |caller: Caller<'_>, buf: i32, len: i32| {
+        let memory = match caller.get_export("memory") {
+            Some(Extern::Memory(memory)) => memory,
+            _ => return 21,
+        };
+        let buf = buf as usize;
+        unsafe { memory.data_unchecked_mut()[buf..buf + len as usize].fill(0x2a) };
+        0
+    });
+    let wasi = AutoWasi::with_overrides(
+        &store,
+        ctx(),
+        WasiVersion::Snapshot1,
+        vec![("random_get", random_get)],
+    )
+    .expect("failed to create wasi");
+
+    let mut linker = Linker::new(&store);
+    wasi.add_to_linker(&mut linker)
+        .expect("failed to add to linker");
+    let module = Module::new(store.engine(), common::fixture("random")).expect("invalid fixture");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+    let random = instance.get_func("random").unwrap().get0::<i32>().unwrap();
+    assert_eq!(random().unwrap(), 0x2a2a2a2a);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn invalid_overrides() {
+    if common::engine_unavailable() {
+        return;
+    }
+    use wasmtime::Func;
+
+    let store = Store::default();
+    let overrides = vec![("fd_frobnicate", Func::wrap(&store, || 0))];
+    match AutoWasi::with_overrides(&store, ctx(), WasiVersion::Snapshot1, overrides) {
+        Err(WasiDetectError::UnknownOverride { name }) => assert_eq!(name, "fd_frobnicate"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let overrides = vec![("sched_yield", Func::wrap(&store, |_: i32| 0))];
+    match AutoWasi::with_overrides(&store, ctx(), WasiVersion::Snapshot1, overrides) {
+        Err(error @ WasiDetectError::OverrideMismatch { .. }) => assert_eq!(
+            error.to_string(),
+            "override of WASI function `sched_yield` has type (func (param i32) (result i32)), \
+             expected (func (result i32))"
+        ),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use auto_wasi::{AutoWasiBuilder, WasiDetectError, WasiVersion};
+use std::path::Path;
+use wasmtime::Store;
+
+#[test]
+fn ctx() {
+    AutoWasiBuilder::new()
+        .args(["test", "--verbose"])
+        .envs([("KEY", "value")])
+        .preopen_dir("tests/data", "/data")
+        .inherit_stdio()
+        .ctx()
+        .expect("failed to build context");
+}
+
+#[test]
+fn conflicting_stdio() {
+    let result = AutoWasiBuilder::new().inherit_stdio().null_stdio().ctx();
+    match result {
+        Err(WasiDetectError::ConflictingOptions { first, second }) => {
+            assert_eq!((first, second), ("inherit_stdio", "null_stdio"))
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+#[cfg(feature = "stdin")]
+fn conflicting_stdin() {
+    let result = AutoWasiBuilder::new().stdin_str("input").null_stdio().ctx();
+    match result {
+        Err(WasiDetectError::ConflictingOptions { first, second }) => {
+            assert_eq!((first, second), ("stdin_bytes", "null_stdio"))
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn missing_preopen_dir() {
+    let result = AutoWasiBuilder::new()
+        .preopen_dir("tests/data/missing", "/data")
+        .ctx();
+    match result {
+        Err(WasiDetectError::Preopen { path, .. }) => {
+            assert_eq!(path, Path::new("tests/data/missing"))
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
+
+#[test]
+fn invalid_env() {
+    let result = AutoWasiBuilder::new().envs([("K\0EY", "value")]).ctx();
+    assert!(matches!(result, Err(WasiDetectError::Ctx(_))));
+}
+
+#[test]
+#[cfg(feature = "snapshot0")]
+fn detect() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &common::fixture("snapshot_0");
+    let wasi = AutoWasiBuilder::new()
+        .args(["test"])
+        .detect(&store, binary)
+        .expect("failed to detect wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot0);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn build() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasi = AutoWasiBuilder::new()
+        .build(&store, WasiVersion::Snapshot1)
+        .expect("failed to create wasi");
+    assert_eq!(wasi.version(), WasiVersion::Snapshot1);
+}
+
+/// Runs `_start` of the fixture with a builder reading the input from stdin, returning stdout.
+#[cfg(all(feature = "snapshot1", feature = "stdin"))]
+fn pipe(fixture: &str, builder: &mut AutoWasiBuilder) -> Vec<u8> {
+    use auto_wasi::CapturedIo;
+    use wasmtime::Module;
+
+    let store = Store::default();
+    let binary = common::fixture(fixture);
+    let captured = CapturedIo::new();
+    let wasi = builder
+        .capture_stdio(&captured)
+        .detect(&store, &binary)
+        .expect("failed to detect wasi");
+    let module = Module::new(store.engine(), &binary).expect("failed to compile module");
+    wasi.into_linker(&store)
+        .expect("failed to add to linker")
+        .instantiate(&module)
+        .expect("failed to instantiate")
+        .get_func("_start")
+        .unwrap()
+        .call(&[])
+        .expect("failed to run");
+    captured.take_stdout()
+}
+
+#[test]
+#[cfg(all(feature = "snapshot1", feature = "stdin"))]
+fn stdin_bytes() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let input = "the quick brown fox jumps over the lazy dog\n".repeat(3);
+    let output = pipe("cat", AutoWasiBuilder::new().stdin_str(&input));
+    assert_eq!(output, input.as_bytes());
+
+    let output = pipe(
+        "cat",
+        AutoWasiBuilder::new().stdin_bytes(vec![0, 159, 146, 150]),
+    );
+    assert_eq!(output, [0, 159, 146, 150]);
+
+    let output = pipe("cat", AutoWasiBuilder::new().stdin_bytes(Vec::new()));
+    assert_eq!(output, b"");
+}
+
+#[test]
+#[cfg(all(feature = "snapshot1", feature = "stdin"))]
+fn stdin_unread() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let output = pipe("hello", AutoWasiBuilder::new().stdin_str("unread"));
+    assert_eq!(output, b"hello\n");
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "cache")]
+
+mod common;
+
+use auto_wasi::{WasiDetectError, WasiVersion, WasiVersionCache};
+use common::fixture;
+
+#[test]
+fn detect_cached() {
+    let cache = WasiVersionCache::new();
+    let snapshot_0 = fixture("snapshot_0");
+    let snapshot_1 = fixture("snapshot_1");
+
+    for _ in 0..2 {
+        let version = cache
+            .detect_cached(&snapshot_0)
+            .expect("invalid wasm binary");
+        assert_eq!(version, WasiVersion::Snapshot0);
+        let version = cache
+            .detect_cached(&snapshot_1)
+            .expect("invalid wasm binary");
+        assert_eq!(version, WasiVersion::Snapshot1);
+    }
+    assert_eq!(cache.len(), 2);
+
+    let result = cache.detect_cached(b"\0asm\x01\0");
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn invalidate() {
+    let cache = WasiVersionCache::new();
+    let snapshot_0 = fixture("snapshot_0");
+    let snapshot_1 = fixture("snapshot_1");
+    cache
+        .detect_cached(&snapshot_0)
+        .expect("invalid wasm binary");
+    cache
+        .detect_cached(&snapshot_1)
+        .expect("invalid wasm binary");
+
+    cache.invalidate(&snapshot_0);
+    assert_eq!(cache.len(), 1);
+    cache.invalidate(&snapshot_0);
+    assert_eq!(cache.len(), 1);
+
+    cache.invalidate_all();
+    assert!(cache.is_empty());
+}
+
+#[test]
+#[cfg(feature = "sync")]
+fn shared_between_threads() {
+    let cache = std::sync::Arc::new(WasiVersionCache::new());
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let cache = cache.clone();
+            std::thread::spawn(move || cache.detect_cached(&fixture("snapshot_0")).unwrap())
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), WasiVersion::Snapshot0);
+    }
+    assert_eq!(cache.len(), 1);
+}
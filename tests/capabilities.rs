@@ -0,0 +1,68 @@
+mod common;
+
+use auto_wasi::{Capability, WasiVersion};
+use common::fixture;
+
+#[test]
+fn stdio_only() {
+    let binary = &fixture("fd_write");
+    let capabilities = WasiVersion::required_capabilities(binary).expect("invalid wasm binary");
+    assert_eq!(capabilities.capabilities(), [Capability::Stdio]);
+    assert!(capabilities.stdio());
+    assert!(!capabilities.filesystem());
+    let functions: Vec<_> = capabilities.functions(Capability::Stdio).collect();
+    assert_eq!(functions, ["fd_write"]);
+}
+
+#[test]
+fn filesystem() {
+    let binary = wat::parse_str(
+        r#"(module
+            (import "wasi_unstable" "path_open" (func))
+            (import "wasi_unstable" "fd_prestat_get" (func))
+            (import "wasi_unstable" "fd_read" (func))
+            (import "wasi_unstable" "clock_time_get" (func))
+            (import "wasi_unstable" "random_get" (func))
+            (import "wasi_unstable" "proc_exit" (func))
+        )"#,
+    )
+    .expect("invalid wasm text");
+    let capabilities = WasiVersion::required_capabilities(binary).expect("invalid wasm binary");
+    assert_eq!(
+        capabilities.capabilities(),
+        [
+            Capability::Stdio,
+            Capability::Filesystem,
+            Capability::Clocks,
+            Capability::Random,
+        ]
+    );
+    let functions: Vec<_> = capabilities.functions(Capability::Filesystem).collect();
+    assert_eq!(functions, ["path_open", "fd_prestat_get"]);
+    assert!(!capabilities.sockets());
+}
+
+#[test]
+fn no_imports() {
+    let binary = &fixture("env");
+    let capabilities = WasiVersion::required_capabilities(binary).expect("invalid wasm binary");
+    assert_eq!(capabilities.capabilities(), []);
+}
+
+#[test]
+fn from_function() {
+    let cases = [
+        ("fd_write", Some(Capability::Stdio)),
+        ("fd_readdir", Some(Capability::Filesystem)),
+        ("path_open", Some(Capability::Filesystem)),
+        ("poll_oneoff", Some(Capability::Clocks)),
+        ("sock_recv", Some(Capability::Sockets)),
+        ("environ_get", Some(Capability::Environment)),
+        ("args_sizes_get", Some(Capability::Args)),
+        ("proc_exit", None),
+        ("sched_yield", None),
+    ];
+    for (name, capability) in &cases {
+        assert_eq!(&Capability::from_function(name), capability, "{}", name);
+    }
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, CapturedIo, WasiDetectError};
+use wasmtime::{Linker, Module, Store};
+
+/// Writes `out` to stdout, `err` to stderr, then `put` to stdout.
+const WRITES: &str = r#"(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\20\00\00\00\03\00\00\00")
+    (data (i32.const 8) "\23\00\00\00\03\00\00\00")
+    (data (i32.const 16) "\26\00\00\00\03\00\00\00")
+    (data (i32.const 32) "outerrput")
+    (func (export "_start")
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 24)))
+        (drop (call $fd_write (i32.const 2) (i32.const 8) (i32.const 1) (i32.const 24)))
+        (drop (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24)))
+    )
+)"#;
+
+#[test]
+fn with_stdio_capture() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasm = wat::parse_str(WRITES).expect("invalid module");
+    let captured = AutoWasi::with_stdio_capture(&store, &wasm).expect("failed to create wasi");
+
+    let mut linker = Linker::new(&store);
+    captured
+        .wasi()
+        .add_to_linker(&mut linker)
+        .expect("failed to add to linker");
+    let module = Module::new(store.engine(), &wasm).expect("failed to compile module");
+    let start = linker
+        .instantiate(&module)
+        .expect("failed to instantiate")
+        .get_func("_start")
+        .unwrap();
+
+    start.call(&[]).expect("failed to run");
+    assert_eq!(captured.take_stdout(), b"output");
+    assert_eq!(captured.take_stderr(), b"err");
+
+    // Taking the output empties the buffers.
+    assert_eq!(captured.take_stdout(), b"");
+    start.call(&[]).expect("failed to run");
+    assert_eq!(captured.take_stdout(), b"output");
+}
+
+/// Runs `_start` of the module with the linker of the instance.
+fn run(store: &Store, wasi: AutoWasi, wasm: &[u8]) {
+    let module = Module::new(store.engine(), wasm).expect("failed to compile module");
+    wasi.into_linker(store)
+        .expect("failed to add to linker")
+        .instantiate(&module)
+        .expect("failed to instantiate")
+        .get_func("_start")
+        .unwrap()
+        .call(&[])
+        .expect("failed to run");
+}
+
+#[test]
+fn detect_captured() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasm = wat::parse_str(WRITES).expect("invalid module");
+    let (wasi, captured) = AutoWasi::detect_captured(&store, &wasm).expect("failed to create wasi");
+
+    run(&store, wasi, &wasm);
+    assert_eq!(captured.stdout(), b"output");
+    assert_eq!(captured.stderr(), b"err");
+    assert_eq!(captured.stdout_lossy(), "output");
+    assert_eq!(captured.stderr_lossy(), "err");
+    captured.check().expect("output exceeded no limit");
+}
+
+#[test]
+fn capture_limit() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasm = wat::parse_str(WRITES).expect("invalid module");
+    let captured = CapturedIo::with_limit(4);
+    let ctx = captured.ctx().expect("failed to build context");
+    let wasi = AutoWasi::detect(&store, ctx, &wasm).expect("failed to create wasi");
+
+    run(&store, wasi, &wasm);
+    assert_eq!(captured.stdout(), b"outp");
+    assert_eq!(captured.stderr(), b"err");
+    assert!(matches!(
+        captured.check(),
+        Err(WasiDetectError::CaptureLimitExceeded {
+            stream: "stdout",
+            limit: 4
+        })
+    ));
+
+    // Emptying the buffer clears the overflow.
+    captured.take_stdout();
+    captured.check().expect("overflow wasn't cleared");
+}
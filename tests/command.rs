@@ -0,0 +1,143 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, ModuleKind, WasiDetectError};
+use common::fixture;
+use wasi_common::WasiCtx;
+use wasmtime::{Store, Val};
+
+fn ctx() -> WasiCtx {
+    WasiCtx::new(["test"]).expect("failed to create context")
+}
+
+fn run(wat: &str) -> Result<auto_wasi::ExitStatus, WasiDetectError> {
+    let store = Store::default();
+    let binary = wat::parse_str(wat).unwrap();
+    AutoWasi::run_command(&store, ctx(), &binary)
+}
+
+#[test]
+fn returning_succeeds() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let status = run(r#"(module (func (export "_start")))"#).expect("failed to run");
+    assert_eq!(status.code(), Some(0));
+    assert!(status.success());
+}
+
+#[test]
+fn proc_exit() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let exit = |code: i32| {
+        run(&format!(
+            r#"(module
+                (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+                (func (export "_start") (call $proc_exit (i32.const {})))
+            )"#,
+            code
+        ))
+        .expect("failed to run")
+    };
+
+    let status = exit(0);
+    assert_eq!(status.code(), Some(0));
+    assert!(status.success());
+
+    let status = exit(3);
+    assert_eq!(status.code(), Some(3));
+    assert!(!status.success());
+    assert_eq!(status.to_string(), "exit status: 3");
+}
+
+#[test]
+fn trap() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let result = run(r#"(module (func (export "_start") unreachable))"#);
+    let trap = match result {
+        Err(WasiDetectError::Trap(trap)) => trap,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(trap.i32_exit_status(), None);
+}
+
+#[test]
+fn missing_start() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let result = run(r#"(module
+            (memory (export "memory") 1)
+            (func (export "_start") (param i32))
+            (func (export "main"))
+        )"#);
+    let exports = match result {
+        Err(WasiDetectError::MissingFunction { name, exports }) => {
+            assert_eq!(name, "_start");
+            exports
+        }
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(exports, ["memory", "_start", "main"]);
+}
+
+#[test]
+fn module_kind() {
+    assert_eq!(
+        ModuleKind::detect(fixture("start_export")).unwrap(),
+        ModuleKind::Command
+    );
+    assert_eq!(
+        ModuleKind::detect(fixture("initialize_export")).unwrap(),
+        ModuleKind::Reactor
+    );
+    assert_eq!(
+        ModuleKind::detect(fixture("reactor")).unwrap(),
+        ModuleKind::Reactor
+    );
+    assert_eq!(
+        ModuleKind::detect(fixture("no_imports")).unwrap(),
+        ModuleKind::Reactor
+    );
+    assert!(matches!(
+        ModuleKind::detect(b"\0asm\x01\0"),
+        Err(WasiDetectError::Parse(_))
+    ));
+}
+
+#[test]
+fn reactor() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let reactor = AutoWasi::instantiate_reactor(&store, ctx(), &fixture("reactor"))
+        .expect("failed to instantiate");
+
+    // `_initialize` adds 42 to the global each time it's called.
+    let answer = reactor.get_func("answer").unwrap().get0::<i32>().unwrap();
+    assert_eq!(answer().unwrap(), 42);
+    let results = reactor.call("answer", &[]).expect("failed to call");
+    assert!(matches!(&results[..], [Val::I32(42)]));
+
+    let result = reactor.call("question", &[]);
+    assert!(matches!(
+        result,
+        Err(WasiDetectError::MissingFunction { .. })
+    ));
+}
+
+#[test]
+fn reactor_command_module() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let result = AutoWasi::instantiate_reactor(&store, ctx(), &fixture("start_export"));
+    assert!(matches!(result, Err(WasiDetectError::CommandModule)));
+}
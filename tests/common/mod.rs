@@ -0,0 +1,41 @@
+//! Fixtures shared by the integration tests, compiled from `tests/data/*.wat` when used.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// Compiles the fixture in `tests/data/<name>.wat` to a binary.
+pub fn fixture(name: &str) -> Vec<u8> {
+    wat::parse_file(format!("tests/data/{}.wat", name)).expect("invalid fixture")
+}
+
+/// Compiles the fixture in `tests/data/<name>.wat` to a new file, for tests reading binaries from
+/// disk.
+pub fn fixture_path(name: &str) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let count = COUNT.fetch_add(1, Ordering::Relaxed);
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+        "{}-{}-{}.wasm",
+        name,
+        std::process::id(),
+        count
+    ));
+    fs::write(&path, fixture(name)).expect("failed to write fixture");
+    path
+}
+
+/// Returns whether wasmtime can't create an `Engine` on this host, for tests running guests to
+/// skip, checking once.
+///
+/// wasmtime 0.22 panics creating an `Engine` on CPUs reporting CPUID leaf 7 subleaves.
+pub fn engine_unavailable() -> bool {
+    static UNAVAILABLE: OnceLock<bool> = OnceLock::new();
+    let unavailable =
+        *UNAVAILABLE.get_or_init(|| std::panic::catch_unwind(wasmtime::Engine::default).is_err());
+    if unavailable {
+        eprintln!("skipped, wasmtime can't create an Engine on this host");
+    }
+    unavailable
+}
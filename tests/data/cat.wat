@@ -0,0 +1,33 @@
+;; Copies stdin to stdout 16 bytes at a time, after checking `poll_oneoff` reports stdin as
+;; readable.
+(module
+    (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "poll_oneoff" (func $poll_oneoff (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    ;; The iovec reading into the buffer at 1024.
+    (data (i32.const 0) "\00\04\00\00\10\00\00\00")
+    ;; An `fd_read` subscription to stdin, the events being written at 128.
+    (data (i32.const 72) "\01")
+    (func (export "_start")
+        (local $read i32)
+        (if (call $poll_oneoff (i32.const 64) (i32.const 128) (i32.const 1) (i32.const 8))
+            (then unreachable))
+        (if (i32.ne (i32.load (i32.const 8)) (i32.const 1))
+            (then unreachable))
+        (if (i32.load16_u (i32.const 136))
+            (then unreachable))
+
+        (loop $copy
+            (if (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8))
+                (then unreachable))
+            (local.set $read (i32.load (i32.const 8)))
+            (if (local.get $read)
+                (then
+                    (i32.store (i32.const 16) (i32.const 1024))
+                    (i32.store (i32.const 20) (local.get $read))
+                    (if (call $fd_write (i32.const 1) (i32.const 16) (i32.const 1) (i32.const 24))
+                        (then unreachable))
+                    (br $copy))))
+    )
+)
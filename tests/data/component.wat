@@ -0,0 +1,14 @@
+(component
+    (core module
+        (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+    )
+    (core module)
+    (component
+        (core module
+            (import "wasi_unstable" "proc_exit" (func (param i32)))
+        )
+    )
+    (core module
+        (import "env" "log" (func))
+    )
+)
\ No newline at end of file
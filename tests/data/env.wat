@@ -0,0 +1,4 @@
+(module
+    (type $empty (func))
+    (import "env" "log" (func (type $empty)))
+)
\ No newline at end of file
@@ -0,0 +1,9 @@
+(module
+    (type (func (param i32 i32 i32 i32) (result i32)))
+    (type (func (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func (type 0)))
+    (import "wasi_ephemeral_nn" "load" (func (type 0)))
+    (import "wasi_ephemeral_crypto_common" "options_open" (func (type 1)))
+    (import "wasi" "thread-spawn" (func (type 1)))
+    (import "wasi_ephemeral_nn" "compute" (func (type 1)))
+)
\ No newline at end of file
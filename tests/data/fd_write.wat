@@ -0,0 +1,7 @@
+(module
+    (type $fd_write (func (param i32 i32 i32 i32) (result i32)))
+    (type $proc_exit (func (param i32)))
+    (import "env" "memory" (memory 1))
+    (import "wasi_snapshot_preview1" "fd_write" (func (type $fd_write)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func (type $proc_exit)))
+)
\ No newline at end of file
@@ -0,0 +1,6 @@
+(module
+    (memory (export "memory") 1)
+    (func (export "_initialize"))
+    (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+        i32.const 0)
+)
\ No newline at end of file
@@ -0,0 +1,6 @@
+(module
+    (type $empty (func))
+    (import "wasi_unstable" "fd_write" (func (type $empty)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func (type $empty)))
+    (import "wasi_snapshot_preview1" "random_get" (func (type $empty)))
+)
\ No newline at end of file
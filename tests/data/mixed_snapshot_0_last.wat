@@ -0,0 +1,7 @@
+(module
+    (type $empty (func))
+    (import "wasi_snapshot_preview1" "proc_exit" (func (type $empty)))
+    (import "wasi_snapshot_preview1" "random_get" (func (type $empty)))
+    (import "wasi_snapshot_preview1" "sched_yield" (func (type $empty)))
+    (import "wasi_unstable" "fd_write" (func (type $empty)))
+)
\ No newline at end of file
@@ -0,0 +1,4 @@
+(module
+    (type $empty (func))
+    (import "wasi:cli/environment@0.2.0" "get-environment" (func (type $empty)))
+)
\ No newline at end of file
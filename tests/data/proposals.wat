@@ -0,0 +1,17 @@
+(module
+    (type $point (struct (field i32) (field i32)))
+    (type $print (func (param v128 externref) (result i32 i64)))
+    (import "env" "memory" (memory i64 1))
+    (import "env" "shared" (memory 1 2 shared))
+    (import "env" "table" (table 1 externref))
+    (import "env" "lanes" (global (mut v128)))
+    (import "env" "print" (func (type $print)))
+    (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+    (func (result v128)
+        (v128.const i32x4 0 0 0 0)
+    )
+    (func (param i64)
+        (memory.fill 1 (i32.const 0) (i32.const 0) (i32.const 0))
+        (drop (i32.atomic.load 1 (i32.const 0)))
+    )
+)
\ No newline at end of file
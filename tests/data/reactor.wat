@@ -0,0 +1,9 @@
+(module
+  (global $answer (mut i32) (i32.const 0))
+  (func (export "_initialize")
+    global.get $answer
+    i32.const 42
+    i32.add
+    global.set $answer)
+  (func (export "answer") (result i32)
+    global.get $answer))
\ No newline at end of file
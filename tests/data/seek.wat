@@ -0,0 +1,10 @@
+(module
+    (import "wasi_unstable" "fd_seek" (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+    (import "wasi_unstable" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (func (export "seek") (param $whence i32) (result i32)
+        (call $fd_seek (i32.const 3) (i64.const 0) (local.get $whence) (i32.const 0)))
+    (func (export "_start")
+        (drop (call $fd_seek (i32.const 0) (i64.const 0) (i32.const 0) (i32.const 0)))
+        (call $proc_exit (i32.const 3)))
+)
\ No newline at end of file
@@ -0,0 +1,4 @@
+(module
+    (memory (export "memory") 1)
+    (func (export "_start"))
+)
\ No newline at end of file
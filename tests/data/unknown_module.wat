@@ -0,0 +1,5 @@
+(module
+    (type (func (param i32)))
+    (import "env" "log" (func (type 0)))
+    (import "wasi_snapshot_preview2" "proc_exit" (func (type 0)))
+)
\ No newline at end of file
@@ -0,0 +1,9 @@
+(module
+    (type (func (param i32 i32 i32 i32) (result i32)))
+    (type (func (param i32)))
+    (type (func (param i32) (result i32)))
+    (import "env" "memory" (memory 1))
+    (import "wasi_snapshot_preview1" "fd_write" (func (type 0)))
+    (import "wasix_32v1" "thread_spawn" (func (type 2)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func (type 1)))
+)
\ No newline at end of file
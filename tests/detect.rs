@@ -1,15 +1,911 @@
-use auto_wasi::WasiVersion;
+mod common;
+
+use auto_wasi::{
+    DetectLimits, DetectionConfig, UnknownNamespaceAction, WasiDetectError, WasiDetectWarning,
+    WasiVersion, WasiVersionDetector, WasiVersionRequirement,
+};
+use common::{fixture, fixture_path};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 #[test]
 fn detect_snapshot_0() {
-    let binary = include_bytes!("data/snapshot_0.wasm");
+    let binary = &fixture("snapshot_0");
     let version = WasiVersion::detect(binary).expect("invalid wasm binary");
     assert_eq!(version, WasiVersion::Snapshot0);
 }
 
 #[test]
 fn detect_snapshot_1() {
-    let binary = include_bytes!("data/snapshot_1.wasm");
+    let binary = &fixture("snapshot_1");
+    let version = WasiVersion::detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+}
+
+#[test]
+fn detect_preview_2() {
+    let binary = &fixture("preview_2");
+    let version = WasiVersion::detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Preview2);
+}
+
+#[test]
+fn detect_wasix() {
+    let binary = &fixture("wasix");
+    let version = WasiVersion::detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Wasix32v1);
+
+    let versions = WasiVersion::detect_all(binary).expect("invalid wasm binary");
+    assert_eq!(
+        versions.iter().collect::<Vec<_>>(),
+        [WasiVersion::Snapshot1, WasiVersion::Wasix32v1]
+    );
+}
+
+#[test]
+fn detect_proposals() {
+    // Uses GC, memory64, threads, SIMD, reference types, multi-value and multi-memory.
+    let binary = &fixture("proposals");
+    let version = WasiVersion::detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+
+    let version = WasiVersion::detect_from_reader(&binary[..]).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+    assert_eq!(
+        WasiVersion::detect_no_alloc(binary),
+        Some(WasiVersion::Snapshot1)
+    );
+
+    // The fd_write type comes after the GC type, so its signature can't be checked.
+    let mismatches = WasiVersion::validate(binary).expect("invalid wasm binary");
+    assert!(mismatches.is_empty());
+}
+
+#[test]
+fn detect_mixed() {
+    let binary = &fixture("mixed");
+    let error = match WasiVersion::detect(binary) {
+        Err(WasiDetectError::MixedVersions(error)) => error,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(
+        error.imports,
+        vec![
+            (WasiVersion::Snapshot0, vec!["fd_write".to_string()]),
+            (
+                WasiVersion::Snapshot1,
+                vec!["proc_exit".to_string(), "random_get".to_string()]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn try_detect_snapshot_1() {
+    let binary = &fixture("snapshot_1");
+    let version = WasiVersion::try_detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, Some(WasiVersion::Snapshot1));
+}
+
+#[test]
+fn try_detect_env_imports() {
+    let binary = &fixture("env");
+    let version = WasiVersion::try_detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, None);
+}
+
+#[test]
+fn try_detect_no_imports() {
+    let binary = &fixture("no_imports");
+    let version = WasiVersion::try_detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, None);
+}
+
+#[test]
+fn detect_defaults_without_wasi_imports() {
+    let binary = &fixture("env");
     let version = WasiVersion::detect(binary).expect("invalid wasm binary");
     assert_eq!(version, WasiVersion::Snapshot1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn detect_unknown_module() {
+    let binary = &fixture("unknown_module");
+    let module = match WasiVersion::detect(binary) {
+        Err(WasiDetectError::UnknownWasiModule { module }) => module,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(module, "wasi_snapshot_preview2");
+    assert!(matches!(
+        WasiVersion::try_detect(binary),
+        Err(WasiDetectError::UnknownWasiModule { .. })
+    ));
+
+    // Extensions aren't unknown, and neither are modules next to a known version.
+    let binary = wat::parse_str(
+        r#"(module
+            (import "wasi_ephemeral_nn" "load" (func))
+            (import "wasi_snapshot_preview2" "proc_exit" (func))
+            (import "wasi_unstable" "proc_exit" (func))
+        )"#,
+    )
+    .unwrap();
+    assert_eq!(WasiVersion::detect(binary).unwrap(), WasiVersion::Snapshot0);
+
+    let binary = wat::parse_str(r#"(module (import "wasi_ephemeral_nn" "load" (func)))"#).unwrap();
+    assert_eq!(WasiVersion::try_detect(binary).unwrap(), None);
+}
+
+#[test]
+fn detect_with_config() {
+    let default = DetectionConfig::default();
+    let binary = &fixture("snapshot_0");
+    let version = WasiVersion::detect_with_config(binary, &default).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+
+    let binary = &fixture("env");
+    let config = DetectionConfig {
+        fallback: WasiVersion::Snapshot0,
+        ..default
+    };
+    let version = WasiVersion::detect_with_config(binary, &config).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+
+    let config = DetectionConfig {
+        strict: true,
+        ..default
+    };
+    let result = WasiVersion::detect_with_config(binary, &config);
+    assert!(matches!(result, Err(WasiDetectError::NoWasiImports { .. })));
+
+    let binary = &fixture("unknown_module");
+    let result = WasiVersion::detect_with_config(binary, &default);
+    assert!(matches!(
+        result,
+        Err(WasiDetectError::UnknownWasiModule { .. })
+    ));
+    let config = DetectionConfig {
+        unknown_namespaces: UnknownNamespaceAction::Ignore,
+        ..default
+    };
+    let version = WasiVersion::detect_with_config(binary, &config).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+
+    let binary = wat::parse_str(r#"(module (import "wasi_unstable" "fd_future" (func)))"#).unwrap();
+    let config = DetectionConfig {
+        reject_unknown_functions: true,
+        ..default
+    };
+    let result = WasiVersion::detect_with_config(binary, &config);
+    assert!(matches!(
+        result,
+        Err(WasiDetectError::UnknownFunctions { .. })
+    ));
+}
+
+#[test]
+fn detector() {
+    for name in &[
+        "snapshot_0",
+        "snapshot_1",
+        "mixed",
+        "no_imports",
+        "component",
+    ] {
+        let binary = fixture(name);
+        let mut detector = WasiVersionDetector::new();
+        let fed = binary
+            .chunks(3)
+            .find_map(|chunk| detector.feed(chunk))
+            .unwrap_or_else(|| detector.finalize());
+        let expected = WasiVersion::detect_from_reader(&binary[..]);
+        assert_eq!(fed.ok(), expected.ok(), "{}", name);
+    }
+}
+
+#[test]
+fn detector_ends_early() {
+    let binary = fixture("snapshot_0");
+    let mut detector = WasiVersionDetector::new();
+    let (index, version) = binary
+        .chunks(1)
+        .enumerate()
+        .find_map(|(index, chunk)| Some((index, detector.feed(chunk)?)))
+        .expect("detection didn't end");
+    assert_eq!(version.unwrap(), WasiVersion::Snapshot0);
+    assert!(
+        index < binary.len() - 1,
+        "detection needed the whole binary"
+    );
+
+    // Later chunks aren't parsed.
+    let version = detector.feed(b"garbage").expect("detection restarted");
+    assert_eq!(version.unwrap(), WasiVersion::Snapshot0);
+    assert_eq!(detector.finalize().unwrap(), WasiVersion::Snapshot0);
+}
+
+#[test]
+fn detector_errors() {
+    let binary = fixture("snapshot_1");
+    let mut detector = WasiVersionDetector::new();
+    assert!(detector.feed(&binary[..binary.len() / 2]).is_none());
+    let result = detector.finalize();
+    assert!(matches!(result, Err(WasiDetectError::Truncated { .. })));
+
+    let mut detector = WasiVersionDetector::new();
+    let result = detector.feed(b"\0asm\x02\0\0\0");
+    assert!(matches!(result, Some(Err(WasiDetectError::Parse(_)))));
+}
+
+#[test]
+fn detector_after_error() {
+    let mut detector = WasiVersionDetector::new();
+    let error = match detector.feed(b"\0asm\x02\0\0\0") {
+        Some(Err(error)) => error.to_string(),
+        result => panic!("unexpected result: {:?}", result),
+    };
+
+    // The error is returned again instead of parsing what's fed after it.
+    for _ in 0..2 {
+        match detector.feed(&fixture("snapshot_1")) {
+            Some(Err(repeated @ WasiDetectError::Parse(_))) => {
+                assert_eq!(repeated.to_string(), error)
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+    assert!(matches!(
+        detector.finalize(),
+        Err(WasiDetectError::Parse(_))
+    ));
+
+    let mut detector = WasiVersionDetector::new();
+    assert!(detector.feed(&fixture("snapshot_1")[..12]).is_none());
+    assert!(detector.finalize().is_err());
+    assert!(matches!(
+        detector.finalize(),
+        Err(WasiDetectError::Truncated { .. })
+    ));
+}
+
+#[test]
+fn check_requirement() {
+    let requirement = WasiVersionRequirement::at_least(WasiVersion::Snapshot1);
+    requirement
+        .check_binary(fixture("snapshot_1"))
+        .expect("requirement isn't satisfied");
+    requirement
+        .check_binary(fixture("no_imports"))
+        .expect("requirement isn't satisfied");
+
+    let error = match requirement.check_binary(fixture("snapshot_0")) {
+        Err(WasiDetectError::UnsatisfiedRequirement(error)) => error,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(error.found, WasiVersion::Snapshot0);
+    assert_eq!(error.requirement, requirement);
+    assert_eq!(
+        error.to_string(),
+        "binary imports wasi_unstable, which doesn't satisfy the requirement >=snapshot1"
+    );
+
+    let result = requirement.check_binary(b"\0asm\x01\0");
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+}
+
+#[test]
+fn detect_from_exports() {
+    // Exporting the component model's `cabi_realloc` doesn't make a core module a component.
+    let binary = &fixture("initialize_export");
+    let version = WasiVersion::detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+    assert_eq!(WasiVersion::try_detect(binary).unwrap(), None);
+
+    let mut config = DetectionConfig {
+        fallback: WasiVersion::Snapshot0,
+        ..DetectionConfig::default()
+    };
+    let binary = &fixture("start_export");
+    let version = WasiVersion::detect_with_config(binary, &config).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+    let no_imports = &fixture("no_imports");
+    let version =
+        WasiVersion::detect_with_config(no_imports, &config).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+
+    config.export_heuristics = false;
+    let version = WasiVersion::detect_with_config(binary, &config).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+    let version = WasiVersion::detect_with_fallback(binary, WasiVersion::Snapshot0)
+        .expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+}
+
+#[test]
+fn detect_resilient() {
+    let binary = fixture("mixed");
+    let (version, warnings) = WasiVersion::detect_resilient(&binary);
+    assert_eq!(version, None);
+    assert!(matches!(
+        warnings[..],
+        [WasiDetectWarning::MixedVersions(_)]
+    ));
+
+    let binary = fixture("snapshot_0");
+    let (version, warnings) = WasiVersion::detect_resilient(&binary);
+    assert_eq!(version, Some(WasiVersion::Snapshot0));
+    assert!(warnings.is_empty());
+
+    let mut binary = wat::parse_str(
+        r#"(module
+            (import "wasi_unstable" "proc_exit" (func (param i32)))
+            (import "env" "log" (func))
+        )"#,
+    )
+    .unwrap();
+    // Make the length of `env` run past the end of the import section.
+    let name = binary.windows(3).position(|w| w == b"env").unwrap();
+    binary[name - 1] = 0x7f;
+    assert!(WasiVersion::detect(&binary).is_err());
+
+    let (version, warnings) = WasiVersion::detect_resilient(&binary);
+    assert_eq!(version, Some(WasiVersion::Snapshot0));
+    match &warnings[..] {
+        [WasiDetectWarning::MalformedImport { offset, error }] => {
+            assert_eq!(*offset, error.offset());
+            assert!(*offset >= name);
+        }
+        warnings => panic!("unexpected warnings: {:?}", warnings),
+    }
+
+    let (version, warnings) = WasiVersion::detect_resilient(&binary[..name]);
+    assert_eq!(version, None);
+    assert!(matches!(
+        warnings[..],
+        [WasiDetectWarning::MalformedSection { .. }]
+    ));
+}
+
+#[test]
+fn detect_all() {
+    let binary = &fixture("mixed");
+    let versions = WasiVersion::detect_all(binary).expect("invalid wasm binary");
+    assert!(versions.contains(WasiVersion::Snapshot0));
+    assert!(versions.contains(WasiVersion::Snapshot1));
+    assert!(!versions.contains(WasiVersion::Preview2));
+    assert_eq!(versions.len(), 2);
+
+    let binary = &fixture("snapshot_1");
+    let versions = WasiVersion::detect_all(binary).expect("invalid wasm binary");
+    assert_eq!(
+        versions.iter().collect::<Vec<_>>(),
+        [WasiVersion::Snapshot1]
+    );
+
+    let binary = &fixture("env");
+    let versions = WasiVersion::detect_all(binary).expect("invalid wasm binary");
+    assert!(versions.is_empty());
+}
+
+#[test]
+fn detect_all_ignores_order() {
+    let snapshot_0_last = &fixture("mixed_snapshot_0_last");
+    let versions = WasiVersion::detect_all(snapshot_0_last).expect("invalid wasm binary");
+    let mixed = WasiVersion::detect_all(fixture("mixed")).expect("invalid wasm binary");
+    assert_eq!(versions, mixed);
+    assert_eq!(
+        versions.iter().collect::<Vec<_>>(),
+        [WasiVersion::Snapshot0, WasiVersion::Snapshot1]
+    );
+
+    let result = WasiVersion::detect(snapshot_0_last);
+    assert!(matches!(result, Err(WasiDetectError::MixedVersions(_))));
+}
+
+#[test]
+fn detect_component() {
+    let binary = &fixture("component");
+    let version = WasiVersion::detect(binary).expect("invalid component");
+    assert_eq!(version, WasiVersion::Preview2);
+
+    let version = WasiVersion::detect_from_reader(&binary[..]).expect("invalid component");
+    assert_eq!(version, WasiVersion::Preview2);
+
+    let binary = wat::parse_str(r#"(component (core module (import "env" "log" (func))))"#)
+        .expect("invalid wasm text");
+    let result = WasiVersion::detect(&binary);
+    assert!(matches!(result, Err(WasiDetectError::Component)));
+
+    let result = WasiVersion::detect_from_reader(&binary[..]);
+    assert!(matches!(result, Err(WasiDetectError::Component)));
+}
+
+#[test]
+fn detect_adapted_component() {
+    let binary = wat::parse_str(
+        r#"(component
+            (core module $main
+                (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            )
+            (core module $adapter
+                (import "wasi:cli/stdout@0.2.0" "get-stdout" (func (result i32)))
+            )
+        )"#,
+    )
+    .expect("invalid wasm text");
+    let version = WasiVersion::detect(binary).expect("invalid component");
+    assert_eq!(version, WasiVersion::Preview2);
+}
+
+#[test]
+fn detect_in_component() {
+    let binary = &fixture("component");
+    let versions = WasiVersion::detect_in_component(binary).expect("invalid component");
+    assert_eq!(
+        versions,
+        [
+            (0, Some(WasiVersion::Snapshot1)),
+            (1, None),
+            (2, Some(WasiVersion::Snapshot0)),
+            (3, None),
+        ]
+    );
+}
+
+#[test]
+fn detect_in_core_module() {
+    let binary = &fixture("snapshot_0");
+    let versions = WasiVersion::detect_in_component(binary).expect("invalid wasm binary");
+    assert_eq!(versions, [(0, Some(WasiVersion::Snapshot0))]);
+}
+
+#[test]
+fn detect_in_truncated_component() {
+    let binary = &fixture("component");
+    let result = WasiVersion::detect_in_component(&binary[..binary.len() - 1]);
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+}
+
+#[test]
+fn detect_from_imports() {
+    let version = WasiVersion::detect_from_imports(vec![
+        ("env", "log"),
+        ("wasi_snapshot_preview1", "fd_write"),
+        ("wasix_32v1", "thread_spawn"),
+    ]);
+    assert_eq!(version.unwrap(), Some(WasiVersion::Wasix32v1));
+
+    let version = WasiVersion::detect_from_imports(vec![("wasi:cli/stdout@0.2.0", "get-stdout")]);
+    assert_eq!(version.unwrap(), Some(WasiVersion::Preview2));
+
+    let version = WasiVersion::detect_from_imports(vec![("env", "log")]);
+    assert_eq!(version.unwrap(), None);
+
+    let result = WasiVersion::detect_from_imports(vec![
+        ("env", "log"),
+        ("wasi_snapshot_preview2", "proc_exit"),
+    ]);
+    match result {
+        Err(WasiDetectError::UnknownWasiModule { module }) => {
+            assert_eq!(module, "wasi_snapshot_preview2")
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let error = match WasiVersion::detect_from_imports(vec![
+        ("wasi_snapshot_preview1", "proc_exit"),
+        ("wasi_unstable", "fd_write"),
+    ]) {
+        Err(WasiDetectError::MixedVersions(error)) => error,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(
+        error.imports,
+        [
+            (WasiVersion::Snapshot1, vec!["proc_exit".to_string()]),
+            (WasiVersion::Snapshot0, vec!["fd_write".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn detect_with_fallback() {
+    let binary = &fixture("env");
+    let version = WasiVersion::detect_with_fallback(binary, WasiVersion::Snapshot0)
+        .expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+
+    let binary = &fixture("snapshot_1");
+    let version = WasiVersion::detect_with_fallback(binary, WasiVersion::Snapshot0)
+        .expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+}
+
+#[test]
+fn detect_no_alloc() {
+    for binary in &[
+        &fixture("snapshot_0")[..],
+        &fixture("snapshot_1")[..],
+        &fixture("preview_2")[..],
+        &fixture("wasix")[..],
+        &fixture("mixed")[..],
+        &fixture("env")[..],
+        &fixture("no_imports")[..],
+        &fixture("unknown_module")[..],
+        &fixture("start_export")[..],
+        &fixture("initialize_export")[..],
+        &fixture("reactor")[..],
+        &fixture("component")[..],
+        b"\0asm\x01\0",
+    ] {
+        let expected = WasiVersion::detect(binary).ok();
+        assert_eq!(WasiVersion::detect_no_alloc(binary), expected);
+    }
+
+    let binary = wat::parse_str(r#"(component (core module (import "env" "log" (func))))"#)
+        .expect("invalid wasm text");
+    assert_eq!(WasiVersion::detect_no_alloc(&binary), None);
+
+    let name = "a".repeat(DetectLimits::default().max_name_len + 1);
+    let binary = wat::parse_str(format!(r#"(module (import "env" "{}" (func)))"#, name))
+        .expect("invalid wasm text");
+    assert!(matches!(
+        WasiVersion::detect(&binary),
+        Err(WasiDetectError::LimitExceeded { .. })
+    ));
+    assert_eq!(WasiVersion::detect_no_alloc(&binary), None);
+}
+
+#[test]
+fn detect_batch() {
+    let binaries: &[&[u8]] = &[
+        &fixture("snapshot_0"),
+        b"\0asm\x01\0",
+        &fixture("mixed"),
+        &fixture("snapshot_1"),
+    ];
+    let results = WasiVersion::detect_batch(binaries);
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0].as_ref().ok(), Some(&WasiVersion::Snapshot0));
+    assert!(matches!(results[1], Err(WasiDetectError::Parse(_))));
+    assert!(matches!(results[2], Err(WasiDetectError::MixedVersions(_))));
+    assert_eq!(results[3].as_ref().ok(), Some(&WasiVersion::Snapshot1));
+}
+
+#[test]
+fn detect_batch_paths() {
+    let paths = [
+        fixture_path("snapshot_0"),
+        PathBuf::from("tests/data/missing.wasm"),
+        fixture_path("snapshot_1"),
+        PathBuf::from("Cargo.toml"),
+        fixture_path("wasix"),
+    ];
+    let results = WasiVersion::detect_batch_paths(&paths);
+    assert_eq!(results.len(), paths.len());
+    assert_eq!(results[0].as_ref().ok(), Some(&WasiVersion::Snapshot0));
+    assert_eq!(results[2].as_ref().ok(), Some(&WasiVersion::Snapshot1));
+    assert_eq!(results[4].as_ref().ok(), Some(&WasiVersion::Wasix32v1));
+    for &index in &[1, 3] {
+        match &results[index] {
+            Err(WasiDetectError::Path { path, .. }) => assert_eq!(path, &paths[index]),
+            result => panic!("unexpected result for {:?}: {:?}", paths[index], result),
+        }
+    }
+}
+
+#[test]
+fn detect_from_bytes_at_offset() {
+    let binary = fixture("snapshot_0");
+    let mut data = b"header".to_vec();
+    data.extend(&binary);
+    data.extend(b"trailer");
+
+    let version = WasiVersion::detect_from_bytes_at_offset(&data, 6, binary.len())
+        .expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+
+    let result = WasiVersion::detect(&data);
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+
+    for &(offset, length) in &[(6, data.len()), (data.len() + 1, 0), (usize::MAX, 2)] {
+        let error = WasiVersion::detect_from_bytes_at_offset(&data, offset, length)
+            .expect_err("detected out of bounds");
+        match error {
+            WasiDetectError::InvalidRange { len, .. } => assert_eq!(len, data.len()),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+}
+
+#[test]
+fn detect_from_path() {
+    for (name, expected) in &[
+        ("snapshot_0", WasiVersion::Snapshot0),
+        ("snapshot_1", WasiVersion::Snapshot1),
+        ("no_imports", WasiVersion::Snapshot1),
+    ] {
+        let version =
+            WasiVersion::detect_from_path(fixture_path(name)).expect("invalid wasm binary");
+        assert_eq!(&version, expected);
+    }
+}
+
+#[test]
+fn detect_from_path_like_detect() {
+    for name in &["snapshot_0", "initialize_export", "no_imports", "component"] {
+        let version =
+            WasiVersion::detect_from_path(fixture_path(name)).expect("invalid wasm binary");
+        assert_eq!(version, WasiVersion::detect(fixture(name)).unwrap());
+    }
+
+    // Text is only accepted with the `wat` feature, by both.
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join(format!("snapshot_0-{}.wat", std::process::id()));
+    std::fs::copy("tests/data/snapshot_0.wat", &path).expect("failed to copy fixture");
+    let text = std::fs::read(&path).expect("failed to read fixture");
+    let result = WasiVersion::detect_from_path(&path);
+    std::fs::remove_file(&path).expect("failed to remove fixture");
+    match (result, WasiVersion::detect(text)) {
+        (Ok(version), Ok(expected)) => assert_eq!(version, expected),
+        (Err(WasiDetectError::Path { error, .. }), Err(expected)) => {
+            assert_eq!(error.to_string(), expected.to_string())
+        }
+        results => panic!("unexpected results: {:?}", results),
+    }
+}
+
+#[test]
+fn detect_from_missing_path() {
+    let error = WasiVersion::detect_from_path("tests/data/missing.wasm")
+        .expect_err("missing file was detected");
+    assert!(error.to_string().contains("tests/data/missing.wasm"));
+    match error {
+        WasiDetectError::Path { error, .. } => assert!(matches!(*error, WasiDetectError::Io(_))),
+        error => panic!("unexpected error: {:?}", error),
+    }
+}
+
+#[test]
+fn detect_invalid_binary() {
+    let error = WasiVersion::detect(b"\0asm\x01\0").expect_err("invalid binary was detected");
+    assert!(matches!(error, WasiDetectError::Parse(_)));
+}
+
+#[test]
+fn detect_strict_snapshot_0() {
+    let binary = &fixture("snapshot_0");
+    let version = WasiVersion::detect_strict(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot0);
+}
+
+#[test]
+fn detect_strict_env_imports() {
+    let binary = &fixture("env");
+    match WasiVersion::detect_strict(binary) {
+        Err(WasiDetectError::NoWasiImports { namespaces }) => assert_eq!(namespaces, vec!["env"]),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn detect_strict_extension_imports() {
+    let binary = wat::parse_str(r#"(module (import "wasi_ephemeral_nn" "load" (func)))"#)
+        .expect("invalid wasm text");
+    match WasiVersion::detect_strict(binary) {
+        Err(WasiDetectError::NoWasiImports { namespaces }) => {
+            assert_eq!(namespaces, ["wasi_ephemeral_nn"])
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn detect_strict_no_imports() {
+    let binary = &fixture("no_imports");
+    match WasiVersion::detect_strict(binary) {
+        Err(WasiDetectError::NoWasiImports { namespaces }) => assert!(namespaces.is_empty()),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn module_name_round_trip() {
+    for version in &[
+        WasiVersion::Snapshot0,
+        WasiVersion::Snapshot1,
+        WasiVersion::Preview2,
+        WasiVersion::Wasix32v1,
+        WasiVersion::Wasix64v1,
+    ] {
+        let name = version.module_name();
+        assert_eq!(WasiVersion::from_module_name(name).as_ref(), Some(version));
+    }
+}
+
+#[test]
+fn from_module_name() {
+    assert_eq!(
+        WasiVersion::from_module_name("wasi_unstable"),
+        Some(WasiVersion::Snapshot0)
+    );
+    assert_eq!(
+        WasiVersion::from_module_name("wasi:io/streams"),
+        Some(WasiVersion::Preview2)
+    );
+    assert_eq!(WasiVersion::from_module_name("env"), None);
+    assert_eq!(WasiVersion::from_module_name("wasi"), None);
+}
+
+#[test]
+fn display_round_trip() {
+    for version in &[
+        WasiVersion::Snapshot0,
+        WasiVersion::Snapshot1,
+        WasiVersion::Preview2,
+        WasiVersion::Wasix32v1,
+        WasiVersion::Wasix64v1,
+    ] {
+        let parsed: WasiVersion = version
+            .to_string()
+            .parse()
+            .expect("failed to parse version");
+        assert_eq!(&parsed, version);
+    }
+}
+
+#[test]
+fn parse_module_name() {
+    let version: WasiVersion = "wasi_unstable".parse().expect("failed to parse version");
+    assert_eq!(version, WasiVersion::Snapshot0);
+}
+
+#[test]
+fn parse_alias() {
+    for (alias, expected) in &[
+        ("preview1", WasiVersion::Snapshot1),
+        ("wasip1", WasiVersion::Snapshot1),
+        ("wasip2", WasiVersion::Preview2),
+    ] {
+        let version: WasiVersion = alias.parse().expect("failed to parse version");
+        assert_eq!(&version, expected);
+    }
+}
+
+#[test]
+fn try_from() {
+    let cases = [
+        ("snapshot0", WasiVersion::Snapshot0),
+        ("snapshot1", WasiVersion::Snapshot1),
+        ("wasi_unstable", WasiVersion::Snapshot0),
+        ("wasi_snapshot_preview1", WasiVersion::Snapshot1),
+    ];
+    for (name, version) in &cases {
+        assert_eq!(WasiVersion::try_from(*name).as_ref(), Ok(version));
+        assert_eq!(
+            WasiVersion::try_from(name.to_string()).as_ref(),
+            Ok(version)
+        );
+    }
+
+    let error =
+        WasiVersion::try_from("snapshot3".to_string()).expect_err("converted an unknown version");
+    assert_eq!(error.input, "snapshot3");
+}
+
+#[test]
+fn parse_unknown() {
+    let error = "snapshot3"
+        .parse::<WasiVersion>()
+        .expect_err("parsed an unknown version");
+    assert_eq!(error.input, "snapshot3");
+    assert!(error.to_string().contains("snapshot3"));
+}
+
+fn leb128(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    while value >= 0x80 {
+        bytes.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+    bytes
+}
+
+#[test]
+fn detect_stops_after_imports() {
+    let mut binary = fixture("snapshot_0");
+    // A data section claiming to be 64 MiB long that's cut short, so it can only be detected
+    // without parsing it.
+    binary.push(11);
+    binary.extend(leb128(64 * 1024 * 1024));
+    binary.extend(&[0; 16]);
+
+    let version = WasiVersion::detect(binary).expect("parsed past the import section");
+    assert_eq!(version, WasiVersion::Snapshot0);
+}
+
+#[test]
+fn detect_large_binary() {
+    let data = vec![0; 32 * 1024 * 1024];
+    // A single active segment for the imported memory at offset 0.
+    let mut section = vec![1, 0, 0x41, 0, 0x0b];
+    section.extend(leb128(data.len()));
+    section.extend(&data);
+
+    let mut binary = fixture("fd_write");
+    binary.push(11);
+    binary.extend(leb128(section.len()));
+    binary.extend(section);
+
+    let version = WasiVersion::detect(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+}
+
+/// Returns a module importing `fd_write` with a million empty functions, about 4 MiB.
+fn many_functions() -> Vec<u8> {
+    const FUNCTIONS: usize = 1_000_000;
+    let section = |id: u8, contents: Vec<u8>| {
+        let mut section = vec![id];
+        section.extend(leb128(contents.len()));
+        section.extend(contents);
+        section
+    };
+
+    let mut binary = b"\0asm\x01\0\0\0".to_vec();
+    // `(func (param i32 i32 i32 i32) (result i32))` and `(func)`.
+    let types = vec![2, 0x60, 4, 0x7f, 0x7f, 0x7f, 0x7f, 1, 0x7f, 0x60, 0, 0];
+    binary.extend(section(1, types));
+    let mut imports = vec![1];
+    for name in &["wasi_snapshot_preview1", "fd_write"] {
+        imports.extend(leb128(name.len()));
+        imports.extend(name.as_bytes());
+    }
+    imports.extend(&[0, 0]);
+    binary.extend(section(2, imports));
+    let mut functions = leb128(FUNCTIONS);
+    functions.resize(functions.len() + FUNCTIONS, 1);
+    binary.extend(section(3, functions));
+    let mut code = leb128(FUNCTIONS);
+    for _ in 0..FUNCTIONS {
+        code.extend(&[2, 0, 0x0b]);
+    }
+    binary.extend(section(10, code));
+    binary
+}
+
+/// Returns the shortest of a few runs of `f`.
+fn fastest(mut f: impl FnMut()) -> Duration {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+#[test]
+fn detect_large_binary_stops_early() {
+    let binary = many_functions();
+
+    // Detection used to walk every payload of the binary.
+    let parse_all = fastest(|| {
+        for payload in wasmparser::Parser::new(0).parse_all(&binary) {
+            payload.expect("invalid wasm binary");
+        }
+    });
+    let detect = fastest(|| {
+        let version = WasiVersion::detect(&binary).expect("invalid wasm binary");
+        assert_eq!(version, WasiVersion::Snapshot1);
+    });
+
+    assert!(
+        detect * 10 < parse_all,
+        "detect took {:?}, walking the binary {:?}",
+        detect,
+        parse_all
+    );
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, DeterministicConfig, WasiVersion};
+use std::convert::TryInto;
+use std::io::Cursor;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+use wasi_common::virtfs::pipe::WritePipe;
+use wasi_common::WasiCtxBuilder;
+use wasmtime::{Module, Store};
+
+/// Writes the realtime and monotonic clocks followed by 12 random bytes to stdout.
+const PRINT: &str = r#"(module
+    (import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\10\00\00\00\1c\00\00\00")
+    (func (export "_start")
+        (drop (call $clock_time_get (i32.const 0) (i64.const 0) (i32.const 16)))
+        (drop (call $clock_time_get (i32.const 1) (i64.const 0) (i32.const 24)))
+        (drop (call $random_get (i32.const 32) (i32.const 12)))
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+    )
+)"#;
+
+fn run(config: DeterministicConfig) -> Vec<u8> {
+    let store = Store::default();
+    let stdout = Arc::new(RwLock::new(Cursor::new(Vec::new())));
+    let ctx = WasiCtxBuilder::new()
+        .stdout(WritePipe::from_shared(stdout.clone()))
+        .build()
+        .expect("failed to create context");
+    let wasi = AutoWasi::deterministic(&store, ctx, WasiVersion::Snapshot1, config)
+        .expect("failed to create wasi");
+
+    let module = Module::new(store.engine(), PRINT).expect("failed to compile module");
+    let instance = wasi
+        .into_linker(&store)
+        .expect("failed to create linker")
+        .instantiate(&module)
+        .expect("failed to instantiate");
+    instance
+        .get_func("_start")
+        .unwrap()
+        .call(&[])
+        .expect("failed to run");
+
+    let output = stdout.read().unwrap().get_ref().clone();
+    output
+}
+
+#[test]
+fn deterministic() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let config = DeterministicConfig {
+        start_time: UNIX_EPOCH + Duration::from_secs(1),
+        seed: 7,
+        tick: Duration::from_nanos(10),
+    };
+    let output = run(config);
+    assert_eq!(output.len(), 28);
+    assert_eq!(output, run(config));
+
+    let realtime = u64::from_le_bytes(output[..8].try_into().unwrap());
+    let monotonic = u64::from_le_bytes(output[8..16].try_into().unwrap());
+    assert_eq!(realtime, 1_000_000_010);
+    assert_eq!(monotonic, 20);
+
+    let reseeded = run(DeterministicConfig { seed: 8, ..config });
+    assert_eq!(reseeded[..16], output[..16]);
+    assert_ne!(reseeded[16..], output[16..]);
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use auto_wasi::{
+    AutoWasi, ExtensionImports, ImportKind, SignatureMismatch, UnresolvedImport, UnresolvedImports,
+    VersionSource, WasiExtension, WasiImport, WasiVersion,
+};
+use common::fixture;
+use wasmtime::{FuncType, ValType};
+
+#[test]
+fn inspect_imports() {
+    let binary = &fixture("fd_write");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert_eq!(report.version, Some(WasiVersion::Snapshot1));
+    assert_eq!(
+        report.imports,
+        vec![
+            WasiImport {
+                module: "wasi_snapshot_preview1".to_string(),
+                name: "fd_write".to_string(),
+                type_index: Some(0),
+            },
+            WasiImport {
+                module: "wasi_snapshot_preview1".to_string(),
+                name: "proc_exit".to_string(),
+                type_index: Some(1),
+            },
+        ]
+    );
+    assert_eq!(report.extensions, []);
+    assert_eq!(
+        report.unresolved,
+        [UnresolvedImports {
+            module: "env".to_string(),
+            imports: vec![UnresolvedImport {
+                name: "memory".to_string(),
+                kind: ImportKind::Memory,
+            }],
+        }]
+    );
+}
+
+#[test]
+fn unresolved_imports() {
+    let binary = wat::parse_str(
+        r#"(module
+            (import "env" "memory" (memory 256 256))
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+            (import "env" "emscripten_notify_memory_growth" (func (param i32)))
+            (import "GOT.mem" "__heap_base" (global (mut i32)))
+            (import "env" "__indirect_function_table" (table 0 funcref))
+        )"#,
+    )
+    .expect("invalid wasm text");
+    let unresolved = AutoWasi::unresolved_imports(binary).expect("invalid wasm binary");
+    let import = |name: &str, kind| UnresolvedImport {
+        name: name.to_string(),
+        kind,
+    };
+    assert_eq!(
+        unresolved,
+        [
+            UnresolvedImports {
+                module: "env".to_string(),
+                imports: vec![
+                    import("memory", ImportKind::Memory),
+                    import("emscripten_notify_memory_growth", ImportKind::Func),
+                    import("__indirect_function_table", ImportKind::Table),
+                ],
+            },
+            UnresolvedImports {
+                module: "GOT.mem".to_string(),
+                imports: vec![import("__heap_base", ImportKind::Global)],
+            },
+        ]
+    );
+}
+
+#[test]
+fn detect_extensions() {
+    let binary = &fixture("extensions");
+    let extensions = WasiVersion::detect_extensions(binary).expect("invalid wasm binary");
+    assert_eq!(
+        extensions,
+        [
+            ExtensionImports {
+                extension: WasiExtension::Nn,
+                module: "wasi_ephemeral_nn".to_string(),
+                names: vec!["load".to_string(), "compute".to_string()],
+            },
+            ExtensionImports {
+                extension: WasiExtension::Crypto,
+                module: "wasi_ephemeral_crypto_common".to_string(),
+                names: vec!["options_open".to_string()],
+            },
+            ExtensionImports {
+                extension: WasiExtension::Threads,
+                module: "wasi".to_string(),
+                names: vec!["thread-spawn".to_string()],
+            },
+        ]
+    );
+
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert_eq!(report.version, Some(WasiVersion::Snapshot1));
+    assert_eq!(report.extensions, extensions);
+}
+
+#[test]
+fn extension_from_import() {
+    assert_eq!(
+        WasiExtension::from_import("wasi", "thread-spawn"),
+        Some(WasiExtension::Threads)
+    );
+    assert_eq!(WasiExtension::from_import("wasi", "other"), None);
+    assert_eq!(WasiExtension::from_import("env", "load"), None);
+    assert_eq!(WasiExtension::Crypto.to_string(), "wasi-crypto");
+}
+
+#[test]
+fn inspect_no_imports() {
+    let binary = &fixture("no_imports");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert_eq!(report.version, None);
+    assert_eq!(report.source, None);
+    assert!(report.imports.is_empty());
+}
+
+#[test]
+fn inspect_exports() {
+    let binary = &fixture("start_export");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert_eq!(report.version, Some(WasiVersion::Snapshot1));
+    assert_eq!(report.source, Some(VersionSource::Exports));
+    assert!(report.imports.is_empty());
+
+    let binary = &fixture("initialize_export");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert_eq!(report.version, Some(WasiVersion::Snapshot1));
+    assert_eq!(report.source, Some(VersionSource::Exports));
+
+    let binary = &fixture("fd_write");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert_eq!(report.source, Some(VersionSource::Imports));
+}
+
+#[test]
+fn unknown_functions() {
+    let binary = wat::parse_str(
+        r#"(module
+            (import "wasi_snapshot_preview1" "sock_accept_v2" (func))
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+            (import "wasi_snapshot_preview1" "sock_accept_v2" (func))
+            (import "env" "host" (func))
+        )"#,
+    )
+    .expect("invalid wasm text");
+    let report = WasiVersion::inspect(&binary).expect("invalid wasm binary");
+    assert_eq!(report.unknown_functions, ["sock_accept_v2"]);
+
+    let error = WasiVersion::detect_known(&binary).expect_err("detected unknown functions");
+    assert_eq!(
+        error.to_string(),
+        "binary imports functions unknown to wasi_snapshot_preview1: sock_accept_v2"
+    );
+
+    let binary = &fixture("fd_write");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert!(report.unknown_functions.is_empty());
+    let version = WasiVersion::detect_known(binary).expect("invalid wasm binary");
+    assert_eq!(version, WasiVersion::Snapshot1);
+
+    let binary = &fixture("wasix");
+    let report = WasiVersion::inspect(binary).expect("invalid wasm binary");
+    assert!(report.unknown_functions.is_empty());
+}
+
+#[test]
+fn validate_signatures() {
+    let binary = &fixture("fd_write");
+    let mismatches = WasiVersion::validate(binary).expect("invalid wasm binary");
+    assert!(mismatches.is_empty());
+}
+
+#[test]
+fn validate_bad_signature() {
+    let binary = &fixture("bad_signature");
+    let mismatches = WasiVersion::validate(binary).expect("invalid wasm binary");
+    assert_eq!(
+        mismatches,
+        vec![SignatureMismatch {
+            name: "fd_write".to_string(),
+            expected: FuncType::new(vec![ValType::I32; 4], vec![ValType::I32]),
+            actual: Some(FuncType::new(vec![ValType::I32; 3], vec![ValType::I32])),
+        }]
+    );
+}
+
+#[test]
+fn validate_wasix() {
+    let binary = &fixture("wasix");
+    let mismatches = WasiVersion::validate(binary).expect("invalid wasm binary");
+    assert_eq!(mismatches, []);
+}
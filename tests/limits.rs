@@ -0,0 +1,81 @@
+use auto_wasi::{DetectLimits, WasiDetectError, WasiVersion};
+
+const LIMITS: DetectLimits = DetectLimits {
+    max_imports: 4,
+    max_name_len: 32,
+    max_bytes: 128,
+};
+
+fn module(imports: &[(&str, &str)]) -> Vec<u8> {
+    let imports: String = imports
+        .iter()
+        .map(|(module, name)| format!(r#"(import "{}" "{}" (func))"#, module, name))
+        .collect();
+    wat::parse_str(format!("(module {})", imports)).expect("invalid wasm text")
+}
+
+fn assert_limit_exceeded(result: Result<WasiVersion, WasiDetectError>, expected: &str) {
+    match result {
+        Err(WasiDetectError::LimitExceeded { limit, .. }) => assert_eq!(limit, expected),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn within_limits() {
+    let binary = module(&[("wasi_unstable", "fd_write"), ("env", "log")]);
+    let version = WasiVersion::detect_with_limits(binary, &LIMITS).expect("exceeded limits");
+    assert_eq!(version, WasiVersion::Snapshot0);
+}
+
+#[test]
+fn max_imports() {
+    let binary = module(&[("env", "f"); 5]);
+    assert_limit_exceeded(
+        WasiVersion::detect_with_limits(binary, &LIMITS),
+        "max_imports",
+    );
+}
+
+#[test]
+fn max_name_len() {
+    let name = "a".repeat(33);
+    let binary = module(&[("wasi_unstable", &name)]);
+    assert_limit_exceeded(
+        WasiVersion::detect_with_limits(&binary, &LIMITS),
+        "max_name_len",
+    );
+
+    let binary = module(&[(&name, "fd_write")]);
+    assert_limit_exceeded(
+        WasiVersion::detect_with_limits(&binary, &LIMITS),
+        "max_name_len",
+    );
+}
+
+#[test]
+fn max_bytes() {
+    let name = "a".repeat(32);
+    let binary = module(&[("wasi_unstable", name.as_str()); 4]);
+    assert!(binary.len() > LIMITS.max_bytes);
+    assert_limit_exceeded(
+        WasiVersion::detect_with_limits(binary, &LIMITS),
+        "max_bytes",
+    );
+}
+
+#[test]
+fn default_max_bytes() {
+    // A custom section before the imports that's one byte over the default limit.
+    let mut binary = b"\0asm\x01\0\0\0".to_vec();
+    let size = DetectLimits::default().max_bytes - binary.len() - 5 + 1;
+    binary.push(0);
+    binary.extend(&[0x80 | (size & 0x7f) as u8, 0x80 | (size >> 7 & 0x7f) as u8]);
+    binary.extend(&[0x80 | (size >> 14 & 0x7f) as u8, (size >> 21) as u8]);
+    binary.push(0);
+    binary.resize(binary.len() + size - 1, 0);
+    assert_eq!(binary.len(), DetectLimits::default().max_bytes + 1);
+
+    assert_limit_exceeded(WasiVersion::detect(&binary), "max_bytes");
+    assert_limit_exceeded(WasiVersion::detect_from_reader(&binary[..]), "max_bytes");
+}
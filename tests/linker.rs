@@ -0,0 +1,358 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, Conflict, ImportKind, WasiDetectError, WasiVersion};
+use wasi_common::WasiCtx;
+use wasmtime::{Func, FuncType, Linker, Module, Store, Val, ValType};
+
+const MISSING: &str = r#"(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func (param i32)))
+    (import "wasi_snapshot_preview1" "fd_future" (func (param i32 i64) (result i32)))
+    (import "env" "host" (func))
+)"#;
+
+fn wasi(store: &Store) -> AutoWasi {
+    let ctx = WasiCtx::new(["test"]).expect("failed to create context");
+    AutoWasi::new(store, ctx, WasiVersion::Snapshot1).expect("failed to create wasi")
+}
+
+#[test]
+fn missing_exports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let module = Module::new(store.engine(), MISSING).expect("failed to compile module");
+    let missing = wasi(&store).missing_exports(&module);
+
+    let names: Vec<_> = missing.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["fd_write", "fd_future"]);
+    assert_eq!(missing[0].ty, Some(FuncType::new([ValType::I32], [])));
+    assert_eq!(
+        missing[0].provided,
+        Some(FuncType::new(vec![ValType::I32; 4], [ValType::I32]))
+    );
+    assert_eq!(missing[1].kind, ImportKind::Func);
+    assert_eq!(missing[1].provided, None);
+    assert_eq!(
+        missing[1].to_string(),
+        "fd_future (func (param i32 i64) (result i32))"
+    );
+}
+
+#[test]
+fn inspect_imports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasm = wat::parse_str(MISSING).expect("invalid module");
+    let report = wasi(&store)
+        .inspect_imports(&wasm)
+        .expect("invalid wasm binary");
+    assert_eq!(report.satisfied, ["proc_exit"]);
+    assert_eq!(report.unsatisfied, ["fd_write", "fd_future"]);
+    assert!(!report.is_satisfied());
+}
+
+#[test]
+fn add_to_linker_for_module() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let module = Module::new(store.engine(), MISSING).expect("failed to compile module");
+    let mut linker = Linker::new(&store);
+    let error = wasi(&store)
+        .add_to_linker_for_module(&mut linker, &module)
+        .expect_err("linked missing exports");
+    let error = match error {
+        WasiDetectError::MissingExports(error) => error,
+        error => panic!("unexpected error: {:?}", error),
+    };
+    assert_eq!(error.version, WasiVersion::Snapshot1);
+    assert_eq!(error.missing.len(), 2);
+    assert_eq!(
+        error.to_string(),
+        "module imports wasi_snapshot_preview1 items that aren't provided: fd_write (func (param \
+         i32)), fd_future (func (param i32 i64) (result i32))"
+    );
+
+    let module = Module::new(
+        store.engine(),
+        r#"(module (import "wasi_snapshot_preview1" "proc_exit" (func (param i32))))"#,
+    )
+    .expect("failed to compile module");
+    wasi(&store)
+        .add_to_linker_for_module(&mut linker, &module)
+        .expect("failed to add to linker");
+}
+
+#[test]
+fn add_to_linker_filtered() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    wasi(&store)
+        .add_to_linker_filtered(&mut linker, &["proc_exit", "sched_yield"])
+        .expect("failed to add to linker");
+
+    let module = Module::new(
+        store.engine(),
+        r#"(module (import "wasi_snapshot_preview1" "proc_exit" (func (param i32))))"#,
+    )
+    .expect("failed to compile module");
+    linker.instantiate(&module).expect("failed to instantiate");
+
+    let module = Module::new(
+        store.engine(),
+        r#"(module (import "wasi_snapshot_preview1" "path_unlink_file" (func (param i32 i32 i32) (result i32))))"#,
+    )
+    .expect("failed to compile module");
+    assert!(
+        linker.instantiate(&module).is_err(),
+        "instantiated with a filtered function"
+    );
+}
+
+#[test]
+fn add_to_linker_filtered_invalid() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    let wasi = wasi(&store);
+
+    let error = wasi
+        .add_to_linker_filtered(&mut linker, &[])
+        .expect_err("linked an empty allowlist");
+    assert_eq!(error.to_string(), "allowlist of WASI functions is empty");
+
+    let error = wasi
+        .add_to_linker_filtered(&mut linker, &["proc_exit", "sock_accept_v2"])
+        .expect_err("linked an unknown function");
+    assert!(matches!(
+        &error,
+        WasiDetectError::InvalidAllowlist { unknown } if unknown == &["sock_accept_v2"]
+    ));
+
+    // Nothing is linked when the allowlist is invalid.
+    wasi.add_to_linker(&mut linker)
+        .expect("failed to add to linker");
+}
+
+#[test]
+fn add_to_linker_named() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    let wasi = wasi(&store);
+
+    let result = wasi.add_to_linker_named(&mut linker, "");
+    assert!(matches!(result, Err(WasiDetectError::EmptyModuleName)));
+
+    wasi.add_to_linker_named(&mut linker, "wasi")
+        .expect("failed to add to linker");
+    let module = Module::new(
+        store.engine(),
+        r#"(module
+            (import "wasi" "proc_exit" (func (param i32)))
+            (import "wasi" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+        )"#,
+    )
+    .expect("failed to compile module");
+    linker.instantiate(&module).expect("failed to instantiate");
+
+    // The canonical module name isn't linked.
+    let module = Module::new(
+        store.engine(),
+        r#"(module (import "wasi_snapshot_preview1" "proc_exit" (func (param i32))))"#,
+    )
+    .expect("failed to compile module");
+    assert!(linker.instantiate(&module).is_err());
+}
+
+#[test]
+#[cfg(feature = "snapshot0")]
+fn add_to_linker_named_snapshot0() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut linker = Linker::new(&store);
+    let ctx = WasiCtx::new(["test"]).expect("failed to create context");
+    let wasi = AutoWasi::new(&store, ctx, WasiVersion::Snapshot0).expect("failed to create wasi");
+    wasi.add_to_linker_named(&mut linker, "wasi")
+        .expect("failed to add to linker");
+
+    let module = Module::new(
+        store.engine(),
+        r#"(module (import "wasi" "proc_exit" (func (param i32))))"#,
+    )
+    .expect("failed to compile module");
+    linker.instantiate(&module).expect("failed to instantiate");
+}
+
+/// Returns a linker overriding `fd_write` and `proc_exit` with functions that don't need memory.
+fn overridden(store: &Store) -> Linker {
+    let mut linker = Linker::new(store);
+    let fd_write = Func::wrap(store, |_: i32, _: i32, _: i32, _: i32| 7);
+    let proc_exit = Func::wrap(store, |_: i32| {});
+    linker
+        .define("wasi_snapshot_preview1", "fd_write", fd_write)
+        .unwrap();
+    linker
+        .define("wasi_snapshot_preview1", "proc_exit", proc_exit)
+        .unwrap();
+    linker
+}
+
+fn call_fd_write(linker: &Linker) -> anyhow::Result<Box<[Val]>> {
+    let fd_write = linker
+        .get_one_by_name("wasi_snapshot_preview1", Some("fd_write"))?
+        .into_func()
+        .unwrap();
+    fd_write.call(&[Val::I32(0), Val::I32(0), Val::I32(0), Val::I32(0)])
+}
+
+#[test]
+fn add_to_linker_checked_error() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut linker = overridden(&store);
+    let result = wasi(&store).add_to_linker_checked(&mut linker, Conflict::Error);
+    let names = match result {
+        Err(WasiDetectError::Conflicts { module, names }) => {
+            assert_eq!(module, "wasi_snapshot_preview1");
+            names
+        }
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(names, ["fd_write", "proc_exit"]);
+
+    // Nothing is linked when the linker already defines functions.
+    assert!(linker
+        .get_one_by_name("wasi_snapshot_preview1", Some("sched_yield"))
+        .is_err());
+
+    let mut linker = Linker::new(&store);
+    wasi(&store)
+        .add_to_linker_checked(&mut linker, Conflict::Error)
+        .expect("failed to add to linker");
+}
+
+#[test]
+fn add_to_linker_checked_keep_existing() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut linker = overridden(&store);
+    wasi(&store)
+        .add_to_linker_checked(&mut linker, Conflict::KeepExisting)
+        .expect("failed to add to linker");
+
+    let results = call_fd_write(&linker).expect("failed to call fd_write");
+    assert!(matches!(&results[..], [Val::I32(7)]));
+    assert!(linker
+        .get_one_by_name("wasi_snapshot_preview1", Some("sched_yield"))
+        .is_ok());
+}
+
+#[test]
+fn add_to_linker_checked_overwrite() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for shadowing in [false, true] {
+        let mut linker = overridden(&store);
+        linker.allow_shadowing(shadowing);
+        wasi(&store)
+            .add_to_linker_checked(&mut linker, Conflict::Overwrite)
+            .expect("failed to add to linker");
+
+        // The WASI `fd_write` traps without the module's memory.
+        assert!(call_fd_write(&linker).is_err());
+        assert!(linker
+            .get_one_by_name("wasi_snapshot_preview1", Some("sched_yield"))
+            .is_ok());
+
+        // The linker's shadowing setting is kept.
+        let result = linker.define("env", "log", Func::wrap(&store, || {}));
+        assert!(result.is_ok());
+        let result = linker.define("env", "log", Func::wrap(&store, || {}));
+        assert_eq!(result.is_ok(), shadowing);
+    }
+}
+
+#[test]
+fn add_to_linker_checked_mismatch() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for on_conflict in [Conflict::Error, Conflict::KeepExisting, Conflict::Overwrite] {
+        let mut linker = Linker::new(&store);
+        let fd_write = Func::wrap(&store, |_: i32| 7);
+        linker
+            .define("wasi_snapshot_preview1", "fd_write", fd_write)
+            .unwrap();
+        let result = wasi(&store).add_to_linker_checked(&mut linker, on_conflict);
+        match result {
+            Err(WasiDetectError::LinkerMismatch {
+                module,
+                name,
+                expected,
+                found,
+            }) => {
+                assert_eq!(module, "wasi_snapshot_preview1");
+                assert_eq!(name, "fd_write");
+                assert_eq!(expected.params().len(), 4);
+                assert_eq!(found.params().len(), 1);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(linker
+            .get_one_by_name("wasi_snapshot_preview1", Some("sched_yield"))
+            .is_err());
+    }
+}
+
+#[test]
+fn into_linker() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let linker = wasi(&store)
+        .into_linker(&store)
+        .expect("failed to create linker");
+    assert!(linker
+        .get_one_by_name("wasi_snapshot_preview1", Some("sched_yield"))
+        .is_ok());
+
+    let mut linker = Linker::new(&store);
+    linker
+        .define("env", "log", Func::wrap(&store, || {}))
+        .unwrap();
+    let linker = wasi(&store)
+        .into_linker_with(linker)
+        .expect("failed to add to linker");
+    assert!(linker.get_one_by_name("env", Some("log")).is_ok());
+    assert!(linker
+        .get_one_by_name("wasi_snapshot_preview1", Some("sched_yield"))
+        .is_ok());
+
+    let result = wasi(&store).into_linker_with(overridden(&store));
+    assert!(matches!(result, Err(WasiDetectError::Link(_))));
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, WasiVersion};
+use std::io::Cursor;
+use std::sync::{Arc, RwLock};
+use wasi_common::virtfs::pipe::WritePipe;
+use wasi_common::WasiCtxBuilder;
+use wasmtime::{Module, Store, Val};
+
+/// Writes `hi` to stdout as many times as `_start`'s argument says, then exits with 3.
+fn writes(version: WasiVersion) -> String {
+    format!(
+        r#"(module
+            (import "{0}" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (import "{0}" "proc_exit" (func $proc_exit (param i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\08\00\00\00\02\00\00\00")
+            (data (i32.const 8) "hi")
+            (func (export "run") (param $count i32)
+                (loop $write
+                    (if (local.get $count)
+                        (then
+                            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 16)))
+                            (local.set $count (i32.sub (local.get $count) (i32.const 1)))
+                            (br $write))))
+                (call $proc_exit (i32.const 3))
+            )
+        )"#,
+        version.module_name()
+    )
+}
+
+#[test]
+fn call_count() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let versions = [
+        #[cfg(feature = "snapshot0")]
+        WasiVersion::Snapshot0,
+        #[cfg(feature = "snapshot1")]
+        WasiVersion::Snapshot1,
+    ];
+    for version in versions.iter().copied() {
+        let store = Store::default();
+        let stdout = Arc::new(RwLock::new(Cursor::new(Vec::new())));
+        let ctx = WasiCtxBuilder::new()
+            .stdout(WritePipe::from_shared(stdout.clone()))
+            .build()
+            .expect("failed to create context");
+        let (wasi, metrics) =
+            AutoWasi::with_metrics(&store, ctx, version).expect("failed to create wasi");
+
+        let module = Module::new(store.engine(), writes(version)).expect("invalid module");
+        let instance = wasi
+            .into_linker(&store)
+            .expect("failed to create linker")
+            .instantiate(&module)
+            .expect("failed to instantiate");
+        let trap = instance
+            .get_func("run")
+            .unwrap()
+            .call(&[Val::I32(5)])
+            .unwrap_err()
+            .downcast::<wasmtime::Trap>()
+            .unwrap();
+        assert_eq!(trap.i32_exit_status(), Some(3));
+
+        assert_eq!(stdout.read().unwrap().get_ref(), b"hihihihihi");
+        assert_eq!(metrics.call_count("fd_write"), 5);
+        assert_eq!(metrics.call_count("proc_exit"), 1);
+        assert_eq!(metrics.call_count("fd_read"), 0);
+        assert_eq!(metrics.call_count("fd_frobnicate"), 0);
+        let called: Vec<_> = metrics
+            .iter()
+            .map(|(name, calls, _)| (name, calls))
+            .collect();
+        assert_eq!(called, [("fd_write", 5), ("proc_exit", 1)]);
+        assert_eq!(
+            metrics.iter().next().unwrap().2,
+            metrics.total_duration("fd_write")
+        );
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, AutoWasiBuilder};
+use common::fixture;
+use wasmtime::{Linker, Module, Store};
+
+/// Returns the errno of `fd_prestat_get` for the first preopen descriptor, linked from `wasi`.
+fn prestat_errno(store: &Store, wasi: &AutoWasi) -> i32 {
+    let module = Module::new(store.engine(), PRESTAT).expect("failed to compile module");
+    let mut linker = Linker::new(store);
+    wasi.add_to_linker(&mut linker)
+        .expect("failed to add wasi to linker");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+    let prestat = instance
+        .get_func("prestat")
+        .expect("missing export")
+        .get0::<i32>()
+        .expect("wrong signature");
+    prestat().expect("failed to call prestat")
+}
+
+const PRESTAT: &str = r#"(module
+    (import "wasi_snapshot_preview1" "fd_prestat_get" (func $prestat (param i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (func (export "prestat") (result i32)
+        (call $prestat (i32.const 3) (i32.const 0))
+    )
+)"#;
+
+#[test]
+fn detect_minimal() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut policy = AutoWasiBuilder::new();
+    policy.preopen_dir("tests/data", "/data");
+
+    let binary = &fixture("fd_write");
+    let wasi = AutoWasi::detect_minimal(&store, binary, &policy).expect("failed to detect wasi");
+    // EBADF, the stdio-only module doesn't get the preopened directory.
+    assert_eq!(prestat_errno(&store, &wasi), 8);
+
+    let binary = wat::parse_str(PRESTAT).expect("invalid wasm text");
+    let wasi = AutoWasi::detect_minimal(&store, binary, &policy).expect("failed to detect wasi");
+    assert_eq!(prestat_errno(&store, &wasi), 0);
+}
@@ -0,0 +1,130 @@
+#![cfg(all(feature = "snapshot0", feature = "snapshot1"))]
+
+mod common;
+
+use auto_wasi::{AutoWasi, AutoWasiBuilder, MixedWasi, WasiDetectError, WasiVersion};
+use std::io::Cursor;
+use std::sync::{Arc, RwLock};
+use wasi_common::virtfs::pipe::WritePipe;
+use wasi_common::{WasiCtx, WasiCtxBuilder};
+use wasmtime::{Linker, Module, Store};
+
+/// Writes `unstable` to stdout through `wasi_unstable`, then `preview1` through
+/// `wasi_snapshot_preview1`.
+const MIXED: &str = r#"(module
+    (import "wasi_unstable" "fd_write" (func $fd_write0 (param i32 i32 i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write1 (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\10\00\00\00\08\00\00\00")
+    (data (i32.const 8) "\18\00\00\00\08\00\00\00")
+    (data (i32.const 16) "unstable")
+    (data (i32.const 24) "preview1")
+    (func (export "_start")
+        (drop (call $fd_write0 (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 32)))
+        (drop (call $fd_write1 (i32.const 1) (i32.const 8) (i32.const 1) (i32.const 32)))
+    )
+)"#;
+
+fn ctx(stdout: &Arc<RwLock<Cursor<Vec<u8>>>>) -> WasiCtx {
+    WasiCtxBuilder::new()
+        .stdout(WritePipe::from_shared(stdout.clone()))
+        .build()
+        .expect("failed to create context")
+}
+
+#[test]
+fn shared_stdout() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let stdout = Arc::new(RwLock::new(Cursor::new(Vec::new())));
+    let wasi = AutoWasi::new_all(&store, ctx(&stdout));
+
+    let mut linker = Linker::new(&store);
+    wasi.add_to_linker(&mut linker)
+        .expect("failed to add to linker");
+    let module = Module::new(store.engine(), MIXED).expect("failed to compile module");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+    instance
+        .get_func("_start")
+        .unwrap()
+        .call(&[])
+        .expect("failed to run");
+
+    assert_eq!(stdout.read().unwrap().get_ref(), b"unstablepreview1");
+}
+
+/// Closes stdout through `wasi_unstable`, then returns the errno of writing to it through
+/// `wasi_snapshot_preview1`.
+const CLOSE_THEN_WRITE: &str = r#"(module
+    (import "wasi_unstable" "fd_close" (func $fd_close0 (param i32) (result i32)))
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write1 (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\08\00\00\00\08\00\00\00")
+    (data (i32.const 8) "preview1")
+    (func (export "run") (result i32)
+        (drop (call $fd_close0 (i32.const 1)))
+        (call $fd_write1 (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 16))
+    )
+)"#;
+
+#[test]
+fn shared_fd_table() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let stdout = Arc::new(RwLock::new(Cursor::new(Vec::new())));
+    let wasi = MixedWasi::new(&store, ctx(&stdout));
+
+    let mut linker = Linker::new(&store);
+    wasi.add_to_linker(&mut linker)
+        .expect("failed to add to linker");
+    let module = Module::new(store.engine(), CLOSE_THEN_WRITE).expect("failed to compile module");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+    let errno = instance
+        .get_func("run")
+        .unwrap()
+        .call(&[])
+        .expect("failed to run");
+
+    // EBADF, the fd closed through the other snapshot.
+    assert_eq!(errno[0].unwrap_i32(), 8);
+    assert!(stdout.read().unwrap().get_ref().is_empty());
+}
+
+#[test]
+fn get_export() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let wasi = AutoWasiBuilder::new()
+        .build_mixed(&store)
+        .expect("failed to create wasi");
+    assert_eq!(wasi.snapshot0().version(), WasiVersion::Snapshot0);
+    assert_eq!(wasi.snapshot1().version(), WasiVersion::Snapshot1);
+
+    let proc_exit = wasi.get_export("wasi_unstable", "proc_exit").unwrap();
+    assert!(std::ptr::eq(
+        proc_exit,
+        wasi.snapshot0().get_export("proc_exit").unwrap()
+    ));
+    let proc_exit = wasi
+        .get_export("wasi_snapshot_preview1", "proc_exit")
+        .unwrap();
+    assert!(std::ptr::eq(
+        proc_exit,
+        wasi.snapshot1().get_export("proc_exit").unwrap()
+    ));
+    assert!(wasi.get_export("env", "proc_exit").is_none());
+    assert!(wasi.get_export("wasi_unstable", "proc_enter").is_none());
+}
+
+#[test]
+fn mixed_binary_is_rejected() {
+    let binary = wat::parse_str(MIXED).unwrap();
+    let result = WasiVersion::detect(binary);
+    assert!(matches!(result, Err(WasiDetectError::MixedVersions(_))));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use auto_wasi::{AutoWasi, WasiDetectError, WasiVersion};
+use common::fixture;
+use wasi_common::WasiCtx;
+use wasmtime::{Module, Store};
+
+const FIXTURES: [&str; 6] = [
+    "snapshot_0",
+    "snapshot_1",
+    "fd_write",
+    "env",
+    "no_imports",
+    "preview_2",
+];
+
+#[test]
+fn detect_from_module_agrees_with_binary() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for name in FIXTURES.iter().chain(&["unknown_module", "start_export"]) {
+        let binary = &fixture(name);
+        let module = Module::new(store.engine(), binary).expect("failed to compile module");
+        match (
+            WasiVersion::detect_from_module(&module),
+            WasiVersion::detect(binary),
+        ) {
+            (Ok(version), Ok(expected)) => assert_eq!(version, expected, "{}", name),
+            (
+                Err(WasiDetectError::UnknownWasiModule { module }),
+                Err(WasiDetectError::UnknownWasiModule { module: expected }),
+            ) => assert_eq!(module, expected, "{}", name),
+            results => panic!("results of {} disagree: {:?}", name, results),
+        }
+    }
+}
+
+#[test]
+fn detect_from_module_mixed() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let module = Module::new(store.engine(), fixture("mixed")).expect("failed to compile module");
+    let result = WasiVersion::detect_from_module(&module);
+    assert!(matches!(result, Err(WasiDetectError::MixedVersions(_))));
+}
+
+#[test]
+fn detect_precompiled_round_trip() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    for name in &FIXTURES {
+        let binary = &fixture(name);
+        let module = Module::new(store.engine(), binary).expect("failed to compile module");
+        let serialized = module.serialize().expect("failed to serialize module");
+        let result = WasiVersion::detect_precompiled(store.engine(), &serialized);
+        match WasiVersion::detect_strict(binary) {
+            Ok(expected) => assert_eq!(result.expect("failed to detect wasi"), expected),
+            Err(_) => assert!(matches!(result, Err(WasiDetectError::NoWasiImports { .. }))),
+        }
+    }
+}
+
+#[test]
+fn detect_precompiled_invalid() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let binary = &fixture("snapshot_1");
+    let result = WasiVersion::detect_precompiled(store.engine(), binary);
+    assert!(matches!(result, Err(WasiDetectError::Deserialize(_))));
+}
+
+#[test]
+fn for_module() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let (binary, version): (Vec<u8>, _) = if cfg!(feature = "snapshot0") {
+        (fixture("snapshot_0"), WasiVersion::Snapshot0)
+    } else {
+        (fixture("snapshot_1"), WasiVersion::Snapshot1)
+    };
+    let store = Store::default();
+    let module = Module::new(store.engine(), binary).expect("failed to compile module");
+    let ctx = WasiCtx::new(["test"]).expect("failed to create context");
+    let wasi = AutoWasi::for_module(&store, ctx, &module).expect("failed to detect wasi");
+    assert_eq!(wasi.version(), version);
+}
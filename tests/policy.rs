@@ -0,0 +1,93 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, Capability, Denial, Policy, WasiVersion};
+use wasi_common::WasiCtx;
+use wasmtime::{Instance, Module, Store, Trap, Val};
+
+/// Calls `clock_res_get`, `random_get` and `proc_exit`.
+const CALLS: &str = r#"(module
+    (import "wasi_snapshot_preview1" "clock_res_get" (func $clock_res_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+    (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+    (memory (export "memory") 1)
+    (func (export "clock") (result i32)
+        (call $clock_res_get (i32.const 0) (i32.const 0)))
+    (func (export "random") (result i32)
+        (call $random_get (i32.const 0) (i32.const 4)))
+    (func (export "exit")
+        (call $proc_exit (i32.const 0)))
+)"#;
+
+fn instantiate(store: &Store, policy: &Policy) -> Instance {
+    let ctx = WasiCtx::new(["test"]).expect("failed to create context");
+    let wasi = AutoWasi::with_policy(store, ctx, WasiVersion::Snapshot1, policy)
+        .expect("failed to create wasi");
+    let module = Module::new(store.engine(), CALLS).expect("failed to compile module");
+    wasi.into_linker(store)
+        .expect("failed to create linker")
+        .instantiate(&module)
+        .expect("failed to instantiate")
+}
+
+fn call(instance: &Instance, name: &str) -> Result<Box<[Val]>, Trap> {
+    let func = instance.get_func(name).unwrap();
+    func.call(&[])
+        .map_err(|error| error.downcast::<Trap>().unwrap())
+}
+
+fn errno(instance: &Instance, name: &str) -> i32 {
+    call(instance, name).expect("call trapped")[0].unwrap_i32()
+}
+
+#[test]
+fn allows() {
+    let mut policy = Policy::new();
+    assert!(policy.allows("path_open"));
+    assert!(policy.allows("proc_exit"));
+
+    policy.deny(Capability::Filesystem).deny_exit();
+    assert!(!policy.allows("path_open"));
+    assert!(!policy.allows("fd_readdir"));
+    assert!(!policy.allows("proc_exit"));
+    assert!(policy.allows("fd_write"));
+    assert!(policy.allows("sched_yield"));
+}
+
+#[test]
+fn with_policy() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let mut policy = Policy::new();
+    policy.deny(Capability::Clocks).deny_exit();
+    let instance = instantiate(&store, &policy);
+
+    // notcapable
+    assert_eq!(errno(&instance, "clock"), 76);
+    assert_eq!(errno(&instance, "random"), 0);
+    let trap = call(&instance, "exit").expect_err("proc_exit returned");
+    assert_eq!(trap.i32_exit_status(), None);
+    assert!(trap
+        .to_string()
+        .contains("WASI function `proc_exit` is denied by the policy"));
+
+    // nosys
+    policy.denial(Denial::NoSys);
+    let instance = instantiate(&store, &policy);
+    assert_eq!(errno(&instance, "clock"), 52);
+
+    policy.denial(Denial::Trap);
+    let instance = instantiate(&store, &policy);
+    let trap = call(&instance, "clock").expect_err("clock_res_get returned");
+    assert!(trap
+        .to_string()
+        .contains("WASI function `clock_res_get` is denied by the policy"));
+
+    // An allowed proc_exit exits.
+    let instance = instantiate(&store, &Policy::new());
+    let trap = call(&instance, "exit").expect_err("proc_exit returned");
+    assert_eq!(trap.i32_exit_status(), Some(0));
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use auto_wasi::{WasiDetectError, WasiVersion};
+use common::fixture;
+use std::io::{self, Read};
+
+/// A reader that yields at most `chunk_size` bytes per read.
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk_size: usize,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.chunk_size).min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+const FIXTURES: [(&str, WasiVersion); 4] = [
+    ("snapshot_0", WasiVersion::Snapshot0),
+    ("snapshot_1", WasiVersion::Snapshot1),
+    ("fd_write", WasiVersion::Snapshot1),
+    ("no_imports", WasiVersion::Snapshot1),
+];
+
+#[test]
+fn detect_from_reader() {
+    for (name, expected) in &FIXTURES {
+        let binary = fixture(name);
+        let version = WasiVersion::detect_from_reader(&binary[..]).expect("invalid wasm binary");
+        assert_eq!(&version, expected);
+    }
+}
+
+#[test]
+fn detect_from_chunked_reader() {
+    for chunk_size in 1..=8 {
+        for (name, expected) in &FIXTURES {
+            let binary = fixture(name);
+            let reader = ChunkedReader {
+                data: &binary,
+                chunk_size,
+            };
+            let version = WasiVersion::detect_from_reader(reader).expect("invalid wasm binary");
+            assert_eq!(&version, expected, "chunk size {}", chunk_size);
+        }
+    }
+}
+
+#[test]
+fn detect_from_truncated_reader() {
+    let binary = &fixture("fd_write");
+    for len in 0..binary.len() {
+        let reader = ChunkedReader {
+            data: &binary[..len],
+            chunk_size: 7,
+        };
+        // Prefixes ending between sections are valid binaries on their own.
+        match (
+            WasiVersion::detect(&binary[..len]),
+            WasiVersion::detect_from_reader(reader),
+        ) {
+            (Ok(expected), Ok(version)) => assert_eq!(version, expected),
+            (Err(_), Err(WasiDetectError::Truncated { offset })) => assert_eq!(offset, len),
+            (_, result) => panic!("unexpected result for {} bytes: {:?}", len, result),
+        }
+    }
+}
+
+/// A reader that fails after yielding `data`.
+struct FailingReader<'a> {
+    data: &'a [u8],
+}
+
+impl Read for FailingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.data.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+        }
+        let len = buf.len().min(self.data.len());
+        buf[..len].copy_from_slice(&self.data[..len]);
+        self.data = &self.data[len..];
+        Ok(len)
+    }
+}
+
+#[test]
+fn detect_from_failing_reader() {
+    let binary = &fixture("fd_write");
+    let reader = FailingReader {
+        data: &binary[..16],
+    };
+    match WasiVersion::detect_from_reader(reader) {
+        Err(WasiDetectError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::ConnectionReset),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "rewrite")]
+
+mod common;
+
+use auto_wasi::{rewrite, WasiDetectError, WasiVersion};
+use common::fixture;
+use wasmtime::{Func, Linker, Module, Store, Val};
+
+#[test]
+fn upgrade_to_preview1() {
+    for name in &["snapshot_0", "seek"] {
+        let upgraded = rewrite::upgrade_to_preview1(&fixture(name)).expect("failed to upgrade");
+        wasmparser::validate(&upgraded).expect("upgraded binary is invalid");
+        let version = WasiVersion::detect(&upgraded).expect("invalid wasm binary");
+        assert_eq!(version, WasiVersion::Snapshot1, "{}", name);
+    }
+}
+
+#[test]
+fn upgrade_unchanged() {
+    for name in &["snapshot_1", "no_imports"] {
+        let binary = fixture(name);
+        let upgraded = rewrite::upgrade_to_preview1(&binary).expect("failed to upgrade");
+        assert_eq!(upgraded, binary, "{}", name);
+    }
+}
+
+#[test]
+fn not_upgradable() {
+    let binary = wat::parse_str(
+        r#"(module
+            (import "wasi_unstable" "fd_filestat_get" (func (param i32 i32) (result i32)))
+            (import "wasi_unstable" "fd_seek" (func $fd_seek (param i32 i64 i32 i32) (result i32)))
+            (import "wasi_unstable" "poll_oneoff" (func (param i32 i32 i32 i32) (result i32)))
+            (export "seek" (func $fd_seek))
+        )"#,
+    )
+    .unwrap();
+    let functions = match rewrite::upgrade_to_preview1(&binary) {
+        Err(WasiDetectError::NotUpgradable { functions }) => functions,
+        result => panic!("unexpected result: {:?}", result),
+    };
+    assert_eq!(functions, ["fd_filestat_get", "poll_oneoff", "fd_seek"]);
+}
+
+#[test]
+fn seek_adapter() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let upgraded = rewrite::upgrade_to_preview1(&fixture("seek")).expect("failed to upgrade");
+
+    // Returns the `whence` the adapter passes on.
+    let mut linker = Linker::new(&store);
+    let fd_seek = Func::wrap(&store, |_: i32, _: i64, whence: i32, _: i32| whence);
+    linker
+        .define("wasi_snapshot_preview1", "fd_seek", fd_seek)
+        .unwrap();
+    linker
+        .define(
+            "wasi_snapshot_preview1",
+            "proc_exit",
+            Func::wrap(&store, |_: i32| {}),
+        )
+        .unwrap();
+    let module = Module::new(store.engine(), &upgraded).expect("failed to compile module");
+    let seek = linker
+        .instantiate(&module)
+        .expect("failed to instantiate")
+        .get_func("seek")
+        .unwrap();
+
+    // `cur`, `end`, `set` in `wasi_unstable` are `set`, `cur`, `end` in preview1.
+    let whence = |whence: i32| match &seek.call(&[Val::I32(whence)]).unwrap()[..] {
+        [Val::I32(whence)] => *whence,
+        results => panic!("unexpected results: {:?}", results),
+    };
+    assert_eq!(whence(0), 1);
+    assert_eq!(whence(1), 2);
+    assert_eq!(whence(2), 0);
+    assert_eq!(whence(3), 3);
+}
+
+#[test]
+#[cfg(feature = "snapshot1")]
+fn run_upgraded() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let upgraded = rewrite::upgrade_to_preview1(&fixture("seek")).expect("failed to upgrade");
+    let ctx = wasi_common::WasiCtx::new(["test"]).expect("failed to create context");
+    let status = auto_wasi::AutoWasi::run_command(&store, ctx, &upgraded).expect("failed to run");
+    assert_eq!(status.code(), Some(3));
+}
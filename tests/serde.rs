@@ -0,0 +1,69 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use auto_wasi::{DetectionReport, WasiVersion};
+use common::fixture;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Manifest {
+    version: WasiVersion,
+}
+
+#[test]
+fn serialize_version() {
+    let manifest = Manifest {
+        version: WasiVersion::Snapshot1,
+    };
+    assert_eq!(
+        toml::to_string(&manifest).unwrap(),
+        "version = \"wasi_snapshot_preview1\"\n"
+    );
+}
+
+#[test]
+fn version_round_trip() {
+    for version in WasiVersion::all().iter().copied() {
+        let manifest = Manifest { version };
+        let serialized = toml::to_string(&manifest).unwrap();
+        assert_eq!(toml::from_str::<Manifest>(&serialized).unwrap(), manifest);
+    }
+}
+
+#[test]
+fn deserialize_version_alias() {
+    let manifest: Manifest = toml::from_str("version = \"wasip1\"").unwrap();
+    assert_eq!(manifest.version, WasiVersion::Snapshot1);
+}
+
+#[test]
+fn deserialize_unknown_version() {
+    let error = toml::from_str::<Manifest>("version = \"wasi_snapshot_preview9\"").unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("unknown WASI version `wasi_snapshot_preview9`"));
+}
+
+#[test]
+fn report_round_trip() {
+    for name in ["extensions", "env", "no_imports"] {
+        let report = WasiVersion::inspect(fixture(name)).expect("invalid wasm binary");
+        let value = toml::Value::try_from(&report).unwrap();
+        assert_eq!(value.try_into::<DetectionReport>().unwrap(), report);
+    }
+}
+
+#[test]
+fn serialize_report() {
+    let report = WasiVersion::inspect(fixture("extensions")).expect("invalid wasm binary");
+    let value = toml::Value::try_from(&report).unwrap();
+    assert_eq!(value["version"].as_str(), Some("wasi_snapshot_preview1"));
+
+    let extensions = value["extensions"].as_array().unwrap();
+    let extensions: Vec<_> = extensions
+        .iter()
+        .map(|extension| extension["extension"].as_str().unwrap())
+        .collect();
+    assert_eq!(extensions, ["wasi-nn", "wasi-crypto", "wasi-threads"]);
+}
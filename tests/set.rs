@@ -0,0 +1,66 @@
+use auto_wasi::{WasiVersion, WasiVersionSet};
+
+#[test]
+fn insert_and_remove() {
+    let mut set = WasiVersionSet::none();
+    assert!(set.is_empty());
+    assert!(set.insert(WasiVersion::Snapshot0));
+    assert!(!set.insert(WasiVersion::Snapshot0));
+    assert!(set.contains(WasiVersion::Snapshot0));
+    assert_eq!(set.len(), 1);
+
+    assert!(set.remove(WasiVersion::Snapshot0));
+    assert!(!set.remove(WasiVersion::Snapshot0));
+    assert!(set.is_empty());
+}
+
+#[test]
+fn all() {
+    let set = WasiVersionSet::all();
+    assert_eq!(
+        set.into_iter().collect::<Vec<_>>(),
+        [
+            WasiVersion::Snapshot0,
+            WasiVersion::Snapshot1,
+            WasiVersion::Wasix32v1,
+            WasiVersion::Wasix64v1,
+            WasiVersion::Preview2,
+        ]
+    );
+    assert_eq!(set.iter().len(), 5);
+}
+
+#[test]
+fn union_and_intersection() {
+    let host: WasiVersionSet = [WasiVersion::Snapshot0, WasiVersion::Snapshot1]
+        .iter()
+        .copied()
+        .collect();
+    let binary: WasiVersionSet = [WasiVersion::Snapshot1, WasiVersion::Preview2]
+        .iter()
+        .copied()
+        .collect();
+
+    assert_eq!(
+        host.union(&binary).iter().collect::<Vec<_>>(),
+        [
+            WasiVersion::Snapshot0,
+            WasiVersion::Snapshot1,
+            WasiVersion::Preview2,
+        ]
+    );
+    assert_eq!(
+        host.intersection(&binary).iter().collect::<Vec<_>>(),
+        [WasiVersion::Snapshot1]
+    );
+    assert!(host.intersection(&WasiVersionSet::none()).is_empty());
+}
+
+#[test]
+fn debug() {
+    let set: WasiVersionSet = [WasiVersion::Preview2, WasiVersion::Snapshot0]
+        .iter()
+        .copied()
+        .collect();
+    assert_eq!(format!("{:?}", set), "{Snapshot0, Preview2}");
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "snapshot1")]
+
+mod common;
+
+use auto_wasi::{AutoWasi, ImportKind, WasiDetectError, WasiVersion};
+use common::fixture;
+use wasi_common::WasiCtx;
+use wasmtime::{Linker, Module, Store};
+
+const HOST: &str = r#"(module
+    (import "wasi_snapshot_preview1" "proc_exit" (func (param i32)))
+    (import "env" "host" (func $host (param i32) (result i32)))
+    (func (export "call_host") (result i32)
+        (call $host (i32.const 1))
+    )
+    (func (export "answer") (result i32)
+        (i32.const 42)
+    )
+)"#;
+
+fn wasi(store: &Store) -> AutoWasi {
+    let ctx = WasiCtx::new(["test"]).expect("failed to create context");
+    AutoWasi::new(store, ctx, WasiVersion::Snapshot1).expect("failed to create wasi")
+}
+
+#[test]
+fn stub_unknown_imports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let module = Module::new(store.engine(), HOST).expect("failed to compile module");
+    let mut linker = Linker::new(&store);
+    wasi(&store)
+        .add_to_linker_with_stubs(&mut linker, &module)
+        .expect("failed to add stubs");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+
+    let answer = instance.get_func("answer").unwrap().get0::<i32>().unwrap();
+    assert_eq!(answer().expect("failed to call answer"), 42);
+
+    let call_host = instance
+        .get_func("call_host")
+        .unwrap()
+        .get0::<i32>()
+        .unwrap();
+    let trap = call_host().expect_err("called a stub");
+    assert!(trap.to_string().contains("`env.host`"), "{}", trap);
+}
+
+#[test]
+fn stubs_keep_defined_imports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let module = Module::new(store.engine(), HOST).expect("failed to compile module");
+    let mut linker = Linker::new(&store);
+    linker
+        .func("env", "host", |x: i32| x + 1)
+        .expect("failed to define host");
+    wasi(&store)
+        .add_to_linker_with_stubs(&mut linker, &module)
+        .expect("failed to add stubs");
+    let instance = linker.instantiate(&module).expect("failed to instantiate");
+
+    let call_host = instance
+        .get_func("call_host")
+        .unwrap()
+        .get0::<i32>()
+        .unwrap();
+    assert_eq!(call_host().expect("failed to call host"), 2);
+}
+
+#[test]
+fn stubs_reject_memory_imports() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let store = Store::default();
+    let module =
+        Module::new(store.engine(), fixture("fd_write")).expect("failed to compile module");
+    let mut linker = Linker::new(&store);
+    let error = wasi(&store)
+        .add_to_linker_with_stubs(&mut linker, &module)
+        .expect_err("stubbed a memory");
+    assert_eq!(error.to_string(), "can't stub memory import `env.memory`");
+    assert!(matches!(
+        error,
+        WasiDetectError::Unstubbable {
+            kind: ImportKind::Memory,
+            ..
+        }
+    ));
+}
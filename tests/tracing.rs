@@ -0,0 +1,165 @@
+#![cfg(all(feature = "tracing", feature = "snapshot1"))]
+
+mod common;
+
+use auto_wasi::AutoWasi;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use wasi_common::virtfs::pipe::WritePipe;
+use wasi_common::WasiCtxBuilder;
+use wasmtime::{Linker, Module, Store};
+
+/// Writes `hello` to stdout.
+const HELLO: &str = r#"(module
+    (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\08\00\00\00\05\00\00\00")
+    (data (i32.const 8) "hello")
+    (func (export "_start")
+        (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 16)))
+    )
+)"#;
+
+/// The names and debug formatted values of an event's fields.
+type EventFields = Vec<(String, String)>;
+
+/// Collects the `function` field of every span, and the fields of every event.
+#[derive(Default)]
+struct Functions {
+    ids: AtomicU64,
+    functions: Arc<Mutex<Vec<String>>>,
+    events: Arc<Mutex<Vec<EventFields>>>,
+}
+
+struct FunctionField<'a>(&'a Mutex<Vec<String>>);
+
+impl Visit for FunctionField<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "function" {
+            self.0.lock().unwrap().push(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn fmt::Debug) {}
+}
+
+struct Fields(EventFields);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{:?}", value)));
+    }
+}
+
+impl Subscriber for Functions {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        span.record(&mut FunctionField(&self.functions));
+        Id::from_u64(self.ids.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields(Vec::new());
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn guest_calls() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let subscriber = Functions::default();
+    let functions = subscriber.functions.clone();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let store = Store::default();
+        let ctx = WasiCtxBuilder::new()
+            .stdout(WritePipe::new_in_memory())
+            .build()
+            .expect("failed to create context");
+        let wasm = wat::parse_str(HELLO).expect("invalid module");
+        let wasi = AutoWasi::detect(&store, ctx, &wasm).expect("failed to create wasi");
+
+        let mut linker = Linker::new(&store);
+        wasi.add_to_linker(&mut linker)
+            .expect("failed to add to linker");
+        let module = Module::new(store.engine(), &wasm).expect("failed to compile module");
+        let instance = linker.instantiate(&module).expect("failed to instantiate");
+        instance
+            .get_func("_start")
+            .unwrap()
+            .call(&[])
+            .expect("failed to run");
+    });
+
+    assert!(functions
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|name| name == "fd_write"));
+}
+
+#[test]
+fn traced_calls() {
+    if common::engine_unavailable() {
+        return;
+    }
+    let subscriber = Functions::default();
+    let events = subscriber.events.clone();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let store = Store::default();
+        let ctx = WasiCtxBuilder::new()
+            .stdout(WritePipe::new_in_memory())
+            .build()
+            .expect("failed to create context");
+        let wasm = wat::parse_str(HELLO).expect("invalid module");
+        let wasi = AutoWasi::detect_traced(&store, ctx, &wasm).expect("failed to create wasi");
+
+        let mut linker = Linker::new(&store);
+        wasi.add_to_linker(&mut linker)
+            .expect("failed to add to linker");
+        let module = Module::new(store.engine(), &wasm).expect("failed to compile module");
+        let instance = linker.instantiate(&module).expect("failed to instantiate");
+        instance
+            .get_func("_start")
+            .unwrap()
+            .call(&[])
+            .expect("failed to run");
+    });
+
+    let fields = |fields: &[(&str, &str)]| {
+        fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let fd_write = fields(&[
+        ("message", "guest called WASI function"),
+        ("module", "\"wasi_snapshot_preview1\""),
+        ("function", "\"fd_write\""),
+        ("fd", "1"),
+        ("iovs", "0"),
+        ("iovs_len", "1"),
+        ("nwritten", "16"),
+    ]);
+    assert!(events.lock().unwrap().contains(&fd_write));
+}
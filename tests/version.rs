@@ -0,0 +1,171 @@
+use auto_wasi::{UnknownWasiVersion, WasiVersion, WasiVersionRequirement};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+
+const VERSIONS: [WasiVersion; 5] = [
+    WasiVersion::Snapshot0,
+    WasiVersion::Snapshot1,
+    WasiVersion::Wasix32v1,
+    WasiVersion::Wasix64v1,
+    WasiVersion::Preview2,
+];
+
+#[test]
+fn ordered_by_release() {
+    assert!(WasiVersion::Snapshot0 < WasiVersion::Snapshot1);
+    assert!(WasiVersion::Snapshot1 < WasiVersion::Preview2);
+    assert!(WasiVersion::Snapshot1 < WasiVersion::Wasix32v1);
+    assert!(WasiVersion::Wasix32v1 < WasiVersion::Wasix64v1);
+    assert!(WasiVersion::Wasix64v1 < WasiVersion::Preview2);
+    assert!(WasiVersion::Snapshot1 >= WasiVersion::Snapshot1);
+}
+
+#[test]
+fn ordering_is_transitive() {
+    for a in &VERSIONS {
+        for b in &VERSIONS {
+            for c in &VERSIONS {
+                if a <= b && b <= c {
+                    assert!(a <= c, "{} <= {} <= {} but not {} <= {}", a, b, c, a, c);
+                }
+                if a < b && b < c {
+                    assert!(a < c, "{} < {} < {} but not {} < {}", a, b, c, a, c);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn ordering_agrees_with_eq() {
+    for a in &VERSIONS {
+        for b in &VERSIONS {
+            assert_eq!(a == b, a.cmp(b) == std::cmp::Ordering::Equal);
+            assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+        }
+    }
+}
+
+#[test]
+fn btree_map_key() {
+    let map: BTreeMap<_, _> = VERSIONS.iter().rev().map(|v| (*v, v.to_string())).collect();
+    let keys: Vec<_> = map.keys().copied().collect();
+    assert_eq!(keys, VERSIONS.to_vec());
+}
+
+fn hash(version: &WasiVersion) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    version.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn hash_agrees_with_eq() {
+    for a in &VERSIONS {
+        for b in &VERSIONS {
+            if a == b {
+                assert_eq!(hash(a), hash(b), "{} == {} but their hashes differ", a, b);
+            }
+        }
+
+        let parsed: WasiVersion = a.to_string().parse().expect("failed to parse version");
+        assert_eq!(hash(a), hash(&parsed));
+    }
+}
+
+#[test]
+fn hash_map_key() {
+    let map: HashMap<_, _> = VERSIONS.iter().map(|v| (*v, v.to_string())).collect();
+    assert_eq!(map.len(), VERSIONS.len());
+    for version in &VERSIONS {
+        assert_eq!(map[version], version.to_string());
+    }
+}
+
+#[test]
+fn compatible_with() {
+    for guest in &VERSIONS {
+        for host in &VERSIONS {
+            assert_eq!(
+                guest.compatible_with(*host),
+                guest == host,
+                "{} on {}",
+                guest,
+                host
+            );
+        }
+    }
+}
+
+#[test]
+fn abi_version() {
+    assert_eq!(WasiVersion::Snapshot0.abi_version(), 0);
+    assert_eq!(WasiVersion::Snapshot1.abi_version(), 1);
+    assert_eq!(WasiVersion::Preview2.abi_version(), 2);
+    assert_eq!(WasiVersion::Wasix32v1.abi_version(), 3);
+    assert_eq!(WasiVersion::Wasix64v1.abi_version(), 4);
+
+    for version in &VERSIONS {
+        assert_eq!(
+            WasiVersion::try_from(version.abi_version()).as_ref(),
+            Ok(version)
+        );
+    }
+    assert_eq!(WasiVersion::try_from(5), Err(UnknownWasiVersion(5)));
+}
+
+#[test]
+fn is_at_least() {
+    for a in &VERSIONS {
+        for b in &VERSIONS {
+            assert_eq!(a.is_at_least(*b), a >= b, "{} at least {}", a, b);
+        }
+    }
+    assert!(WasiVersion::Snapshot1.is_at_least(WasiVersion::Snapshot0));
+    assert!(!WasiVersion::Snapshot0.is_at_least(WasiVersion::Snapshot1));
+}
+
+#[test]
+fn default_is_latest() {
+    assert_eq!(WasiVersion::default(), WasiVersion::LATEST);
+    assert_eq!(WasiVersion::LATEST, WasiVersion::Snapshot1);
+}
+
+#[test]
+fn all() {
+    assert_eq!(WasiVersion::all(), VERSIONS);
+    assert!(WasiVersion::all().windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn requirement() {
+    let at_least = WasiVersionRequirement::at_least(WasiVersion::Snapshot1);
+    let exact = WasiVersionRequirement::exact(WasiVersion::Snapshot1);
+    for version in VERSIONS.iter() {
+        assert_eq!(
+            at_least.satisfied_by(*version),
+            *version >= WasiVersion::Snapshot1
+        );
+        assert_eq!(
+            exact.satisfied_by(*version),
+            *version == WasiVersion::Snapshot1
+        );
+    }
+    assert_eq!(at_least.to_string(), ">=snapshot1");
+    assert_eq!(exact.to_string(), "=snapshot1");
+}
+
+#[test]
+#[cfg(feature = "arbitrary")]
+fn arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let generated: Vec<_> = (0..=u8::MAX)
+        .map(|byte| WasiVersion::arbitrary(&mut Unstructured::new(&[byte])).unwrap())
+        .collect();
+    for version in WasiVersion::all() {
+        assert!(generated.contains(version));
+    }
+}
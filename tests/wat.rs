@@ -0,0 +1,51 @@
+#![cfg(feature = "wat")]
+
+use auto_wasi::{WasiDetectError, WasiVersion, WasiVersionDetector};
+
+const SNAPSHOT_0: &str = r#"
+(module
+    (import "wasi_unstable" "proc_exit" (func (param i32)))
+)
+"#;
+
+#[test]
+fn detect_wat() {
+    let version = WasiVersion::detect_wat(SNAPSHOT_0).expect("invalid wasm text");
+    assert_eq!(version, WasiVersion::Snapshot0);
+}
+
+#[test]
+fn detect_sniffs_wat() {
+    let version = WasiVersion::detect(SNAPSHOT_0).expect("invalid wasm text");
+    assert_eq!(version, WasiVersion::Snapshot0);
+
+    let report = WasiVersion::inspect(SNAPSHOT_0).expect("invalid wasm text");
+    assert_eq!(report.version, Some(WasiVersion::Snapshot0));
+}
+
+#[test]
+fn detect_invalid_wat() {
+    let wat = "(module\n    (import \"wasi_unstable\" \"proc_exit\" (func (param i33)))\n)";
+    match WasiVersion::detect_wat(wat) {
+        Err(error @ WasiDetectError::Wat(_)) => {
+            assert!(error.to_string().contains(":2:"), "{}", error)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn detect_ignores_other_text() {
+    let result = WasiVersion::detect("(component)");
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+}
+
+#[test]
+fn streaming_rejects_wat() {
+    let result = WasiVersion::detect_from_reader(SNAPSHOT_0.as_bytes());
+    assert!(matches!(result, Err(WasiDetectError::Parse(_))));
+
+    let mut detector = WasiVersionDetector::new();
+    let result = detector.feed(SNAPSHOT_0.as_bytes());
+    assert!(matches!(result, Some(Err(WasiDetectError::Parse(_)))));
+}